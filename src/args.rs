@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fs;
 
+use crate::result::LOSSLESS_FORMAT;
 use crate::utils::{config_path, init_root_path};
 
 // For String.or extension
@@ -38,7 +39,10 @@ pub struct Args {
     #[serde(skip_serializing, skip_deserializing)]
     pub database: String,
 
-    #[options(help = "Output format (e.g., TabSeparatedWithNames, PSQL, JSONLines_Compact, Vertical, ...)")]
+    #[options(
+        help = "Output format (e.g., TabSeparatedWithNames, PSQL, JSONLines_Compact, Vertical, ...) or a client-side \
+                format: markdown, html, csv, asciidoc, latex, unaligned"
+    )]
     #[serde(default)]
     pub format: String,
 
@@ -87,6 +91,18 @@ pub struct Args {
     #[serde(default)]
     pub concise: bool,
 
+    #[options(
+        no_short,
+        help = "Report query time: on, off or detailed (connection, first byte, transfer, server statistics)",
+        meta = "MODE"
+    )]
+    #[serde(default)]
+    pub timing: String,
+
+    #[options(no_short, help = "Write statistics of every statement as JSON lines to a file, or - for stdout", meta = "FILE")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub stats_json: String,
+
     #[options(no_short, help = "Hide URLs that may contain PII in query parameters")]
     #[serde(default)]
     pub hide_pii: bool,
@@ -99,6 +115,94 @@ pub struct Args {
     #[serde(default)]
     pub no_keyring: bool,
 
+    #[options(no_short, help = "Refuse statements other than SELECT, SHOW, DESCRIBE and EXPLAIN")]
+    #[serde(default)]
+    pub read_only: bool,
+
+    #[options(no_short, help = "Named credentials profile to use", meta = "NAME")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub profile: String,
+
+    #[options(no_short, help = "Client-side output option in the form --pset <name>=<value> (see \\pset)", meta = "OPTION")]
+    #[serde(default)]
+    pub pset: Vec<String>,
+
+    #[options(no_short, help = "Expanded (vertical) display of PSQL results: on, off or auto", meta = "MODE")]
+    #[serde(default)]
+    pub expanded: String,
+
+    #[options(
+        no_short,
+        help = "Largest result kept in memory for \\last and \\export, e.g. 64MB; 0 disables (default: 64MB)",
+        meta = "SIZE"
+    )]
+    #[serde(default)]
+    pub max_result_memory: String,
+
+    #[options(no_short, help = "Stop reading a result after this many rows and cancel the query (default: no limit)", meta = "N")]
+    #[serde(default)]
+    pub max_rows: usize,
+
+    #[options(no_short, help = "Stop reading a result after this many bytes, e.g. 10MB, and cancel the query", meta = "SIZE")]
+    #[serde(default)]
+    pub max_bytes: String,
+
+    #[options(no_short, help = "Ask before reading a response larger than this in a terminal; 0 disables (default: 100MB)", meta = "SIZE")]
+    #[serde(default)]
+    pub confirm_bytes: String,
+
+    #[options(
+        no_short,
+        help = "Print each result row through a template, e.g. '{name}: {status|default:-}' (filters: json, sh, default)",
+        meta = "TEMPLATE"
+    )]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub template: String,
+
+    #[options(no_short, help = "Read the --template from a file", meta = "PATH")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub template_file: String,
+
+    #[options(
+        no_short,
+        help = "Plot results in the terminal instead of printing them, e.g. --plot 'bar day total' (see \\plot)",
+        meta = "SPEC"
+    )]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub plot: String,
+
+    #[options(no_short, help = "Print per-column statistics of results instead of the rows (see \\summarize)")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub summarize: bool,
+
+    #[options(no_short, help = "Start the engine without asking when a statement fails because it is not running")]
+    #[serde(default)]
+    pub auto_start_engine: bool,
+
+    #[options(no_short, help = "Wait until fb engine start/stop reaches the target status")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub wait: bool,
+
+    #[options(no_short, help = "Seconds --wait waits for before failing (default: 600)", meta = "SECONDS")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub wait_timeout: u64,
+
+    #[options(no_short, long = "type", help = "Node type for fb engine create/resize, e.g. S, M, L or XL", meta = "TYPE")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub engine_type: String,
+
+    #[options(no_short, help = "Number of nodes for fb engine create/resize", meta = "N")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub nodes: usize,
+
+    #[options(no_short, help = "Number of clusters for fb engine create/resize", meta = "N")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub clusters: usize,
+
+    #[options(no_short, help = "Description of a database created with fb database create", meta = "TEXT")]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub description: String,
+
     #[options(no_short, help = "Update default configuration values")]
     #[serde(skip_serializing, skip_deserializing)]
     pub update_defaults: bool,
@@ -149,6 +253,11 @@ pub fn normalize_extras(extras: Vec<String>, encode: bool) -> Result<Vec<String>
 // Apply defaults and possibly update them.
 #[allow(dead_code)]
 pub fn get_args() -> Result<Args, Box<dyn std::error::Error>> {
+    Ok(get_args_with_config()?.0)
+}
+
+// Like get_args, and also return the extra settings of the config file, which tell where each setting came from.
+pub fn get_args_with_config() -> Result<(Args, Vec<String>), Box<dyn std::error::Error>> {
     let config_path = config_path()?;
 
     let defaults: Args = if config_path.exists() {
@@ -167,8 +276,10 @@ pub fn get_args() -> Result<Args, Box<dyn std::error::Error>> {
     };
 
     args.extra = normalize_extras(args.extra, true)?;
+    let config_extra = normalize_extras(defaults.extra.clone(), true)?;
 
-    // Auto-load saved credentials
+    // Auto-load saved credentials of the selected profile
+    crate::utils::validate_profile(&args.profile)?;
     crate::auth::load_saved_credentials(&mut args)?;
 
     args.jwt_from_file = args.jwt_from_file || defaults.jwt_from_file;
@@ -179,7 +290,7 @@ pub fn get_args() -> Result<Args, Box<dyn std::error::Error>> {
         }
         match fs::read_to_string(&jwt_path) {
             Ok(jwt) => args.jwt = String::from(jwt.trim()),
-            Err(error) => eprintln!("Failed to read jwt from {:?}: {}", &jwt_path, error.to_string()),
+            Err(error) => eprintln!("Failed to read jwt from {:?}: {}", &jwt_path, error),
         }
     }
 
@@ -198,12 +309,53 @@ pub fn get_args() -> Result<Args, Box<dyn std::error::Error>> {
         }
 
         fs::write(&config_path, serde_yaml::to_string(&args)?)?;
-        return Ok(args);
+        return Ok((args, config_extra));
     }
 
     args.verbose = args.verbose || defaults.verbose;
     args.concise = args.concise || defaults.concise;
     args.hide_pii = args.hide_pii || defaults.hide_pii;
+    args.read_only = args.read_only || defaults.read_only;
+    args.auto_start_engine = args.auto_start_engine || defaults.auto_start_engine;
+    args.max_result_memory = args.max_result_memory.or(defaults.max_result_memory).or(String::from("64MB"));
+    crate::utils::parse_size(&args.max_result_memory)?;
+    if args.max_rows == 0 {
+        args.max_rows = defaults.max_rows;
+    }
+    args.max_bytes = args.max_bytes.or(defaults.max_bytes).or(String::from("0"));
+    crate::utils::parse_size(&args.max_bytes)?;
+    args.confirm_bytes = args.confirm_bytes.or(defaults.confirm_bytes).or(String::from("100MB"));
+    crate::utils::parse_size(&args.confirm_bytes)?;
+    // Output options from the command line override those of the config file
+    let mut pset = defaults.pset;
+    pset.append(&mut args.pset);
+    args.pset = pset;
+    crate::render::PrintOptions::from_settings(&args.pset)?;
+
+    args.timing = args.timing.or(defaults.timing);
+    if !args.timing.is_empty() && crate::timing::Timing::parse(&args.timing).is_none() {
+        return Err(format!("Invalid --timing value '{}': expected on, off or detailed", args.timing).into());
+    }
+
+    args.expanded = args.expanded.or(defaults.expanded);
+    if !args.expanded.is_empty() && crate::render::Expanded::parse(&args.expanded).is_none() {
+        return Err(format!("Invalid --expanded value '{}': expected on, off or auto", args.expanded).into());
+    }
+
+    if !args.template_file.is_empty() {
+        if !args.template.is_empty() {
+            return Err("--template and --template-file cannot be used together".into());
+        }
+        let text = fs::read_to_string(&args.template_file)
+            .map_err(|e| format!("Failed to read template file '{}': {}", args.template_file, e))?;
+        // One row per line, the file's own trailing newline is not part of the template
+        args.template = text.strip_suffix('\n').map(|t| t.strip_suffix('\r').unwrap_or(t)).unwrap_or(&text).to_string();
+    } else {
+        args.template = crate::template::unescape(&args.template);
+    }
+    if !args.template.is_empty() {
+        crate::template::Template::parse(&args.template)?;
+    }
 
     if args.core {
         args.host = args.host.or(String::from("localhost:3473"));
@@ -220,7 +372,7 @@ pub fn get_args() -> Result<Args, Box<dyn std::error::Error>> {
         args.extra = normalize_extras(extras, false)?;
     }
 
-    Ok(args)
+    Ok((args, config_extra))
 }
 
 // Create URL from Args
@@ -246,7 +398,9 @@ pub fn get_url(args: &Args) -> String {
     let is_localhost = args.host.starts_with("localhost");
     let protocol = if is_localhost { "http" } else { "https" };
     let output_format = if !args.format.is_empty() && !args.extra.iter().any(|e| e.starts_with("format=")) {
-        format!("&output_format={}", args.format)
+        // Locally rendered formats are requested losslessly
+        let format = if crate::render::local_format(args).is_some() { LOSSLESS_FORMAT } else { args.format.as_str() };
+        format!("&output_format={}", format)
     } else {
         String::new()
     };
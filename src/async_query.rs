@@ -0,0 +1,191 @@
+use serde_json::Value;
+use std::time::Duration;
+use tokio::{select, signal};
+
+use crate::context::Context;
+use crate::meta_commands::{parse_single_row, Row};
+use crate::query::{capture_internal, capture_tsv, query};
+
+// Statuses after which an asynchronous query no longer runs.
+const FINAL_STATUSES: &[&str] = &["ENDED_SUCCESSFULLY", "FAILED", "CANCELLED"];
+const SUCCESS_STATUS: &str = "ENDED_SUCCESSFULLY";
+// Time between status checks of fb wait.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Shortest time between the REPL's checks for finished \async queries, so a quick run of statements does not run
+// a status lookup before every prompt.
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+// Status of an asynchronous query, as returned by fb_GetAsyncStatus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsyncStatus {
+    pub fields: Row,
+}
+
+impl AsyncStatus {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).and_then(|(_, v)| v.as_deref())
+    }
+
+    pub fn status(&self) -> &str {
+        self.field("status").unwrap_or("UNKNOWN")
+    }
+
+    pub fn is_final(&self) -> bool {
+        FINAL_STATUSES.contains(&self.status())
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status() == SUCCESS_STATUS
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.field("error_message").filter(|e| !e.is_empty())
+    }
+}
+
+impl std::fmt::Display for AsyncStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, value) in &self.fields {
+            if let Some(value) = value {
+                writeln!(f, "{:width$}: {}", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Token of an accepted asynchronous query, from the submission response.
+fn parse_token(body: &str) -> Result<String, Box<dyn std::error::Error>> {
+    serde_json::from_str::<Value>(body.trim())
+        .ok()
+        .and_then(|json| json.get("token").and_then(Value::as_str).map(str::to_string))
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| format!("Server did not return an async query token: {}", body.trim()).into())
+}
+
+fn status_query(token: &str) -> String {
+    format!("CALL fb_GetAsyncStatus('{}')", token.replace('\'', "''"))
+}
+
+// Start a query in async execution mode and return its token. A user-set async setting is kept.
+pub async fn submit(context: &mut Context, query_text: String) -> Result<String, Box<dyn std::error::Error>> {
+    let position = context.args.extra.iter().position(|e| e.starts_with("async="));
+    let setting = position.map(|i| context.args.extra.remove(i));
+    context.args.extra.push(String::from("async=true"));
+    let result = capture_tsv(context, query_text).await;
+    context.args.extra.retain(|e| e != "async=true");
+    context.args.extra.extend(setting);
+    context.update_url();
+
+    parse_token(&result?)
+}
+
+// Look up the status of an asynchronous query without reporting time or statistics.
+pub async fn fetch_status(context: &mut Context, token: &str) -> Result<AsyncStatus, Box<dyn std::error::Error>> {
+    let body = capture_internal(context, status_query(token)).await?;
+    Ok(AsyncStatus { fields: parse_single_row(&body)? })
+}
+
+// Poll an asynchronous query until it finishes.
+pub async fn wait(context: &mut Context, token: &str) -> Result<AsyncStatus, Box<dyn std::error::Error>> {
+    let mut last_status = String::new();
+    loop {
+        let status = fetch_status(context, token).await?;
+        if status.is_final() {
+            return Ok(status);
+        }
+        if context.args.verbose && status.status() != last_status {
+            eprintln!("Status: {}", status.status());
+        }
+        last_status = status.status().to_string();
+
+        select! {
+            _ = signal::ctrl_c() => return Err(format!("Interrupted; the query keeps running, see fb status {}", token).into()),
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+// Print the status of an asynchronous query; fails if it did not succeed.
+pub fn print_status(status: &AsyncStatus) -> Result<(), Box<dyn std::error::Error>> {
+    print!("{}", status);
+    if status.is_final() && !status.succeeded() {
+        return Err(match status.error() {
+            Some(error) => format!("Query {}: {}", status.status(), error),
+            None => format!("Query {}", status.status()),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+// Run a statement, or submit it in the background after \async.
+pub async fn run_statement(context: &mut Context, query_text: String) -> Result<(), Box<dyn std::error::Error>> {
+    if !context.async_next {
+        return query(context, query_text).await;
+    }
+
+    context.async_next = false;
+    let token = submit(context, query_text).await?;
+    eprintln!("Submitted asynchronously, token: {}", token);
+    context.async_queries.push(token);
+    Ok(())
+}
+
+// Report asynchronous queries of the session that finished since the last check.
+pub async fn report_finished(context: &mut Context) {
+    let mut running = vec![];
+    for token in std::mem::take(&mut context.async_queries) {
+        match fetch_status(context, &token).await {
+            Ok(status) if status.is_final() => match status.error() {
+                Some(error) => eprintln!("Async query {} {}: {}", token, status.status(), error),
+                None => eprintln!("Async query {} {}", token, status.status()),
+            },
+            Ok(_) => running.push(token),
+            Err(e) => eprintln!("Cannot check async query {}: {}; see fb status {}", token, e, token),
+        }
+    }
+    context.async_queries.extend(running);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(fields: &[(&str, Option<&str>)]) -> AsyncStatus {
+        AsyncStatus { fields: fields.iter().map(|(n, v)| (n.to_string(), v.map(str::to_string))).collect() }
+    }
+
+    #[test]
+    fn test_parse_token() {
+        let body = r#"{"message":"the query was accepted for async processing","monitorSql":"CALL fb_GetAsyncStatus('abc');","token":"abc"}"#;
+        assert_eq!(parse_token(body).unwrap(), "abc");
+
+        let error = parse_token("Error: async execution is not supported for SELECT\n").unwrap_err().to_string();
+        assert_eq!(error, "Server did not return an async query token: Error: async execution is not supported for SELECT");
+        assert!(parse_token(r#"{"token":""}"#).is_err());
+    }
+
+    #[test]
+    fn test_status_query() {
+        assert_eq!(status_query("abc"), "CALL fb_GetAsyncStatus('abc')");
+        assert_eq!(status_query("a'b"), "CALL fb_GetAsyncStatus('a''b')");
+    }
+
+    #[test]
+    fn test_async_status() {
+        let running = status(&[("status", Some("RUNNING")), ("error_message", None)]);
+        assert!(!running.is_final());
+        assert!(print_status(&running).is_ok());
+
+        let done = status(&[("status", Some("ENDED_SUCCESSFULLY")), ("error_message", Some(""))]);
+        assert!(done.is_final() && done.succeeded());
+        assert_eq!(done.error(), None);
+        assert_eq!(done.to_string(), "status       : ENDED_SUCCESSFULLY\nerror_message: \n");
+
+        let failed = status(&[("status", Some("FAILED")), ("error_message", Some("disk full"))]);
+        assert_eq!(print_status(&failed).unwrap_err().to_string(), "Query FAILED: disk full");
+        assert_eq!(status(&[]).status(), "UNKNOWN");
+    }
+}
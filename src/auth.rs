@@ -78,7 +78,17 @@ fn delete_secret_from_file(key: &str) {
     }
 }
 
-fn keyring_store(key: &str, value: &str, no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
+// Secrets of named profiles are stored under "<profile>/<key>".
+fn profile_key(profile: &str, key: &str) -> String {
+    if profile.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}/{}", profile, key)
+    }
+}
+
+fn keyring_store(profile: &str, key: &str, value: &str, no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let key = &profile_key(profile, key);
     if no_keyring {
         return store_secret_in_file(key, value);
     }
@@ -86,7 +96,8 @@ fn keyring_store(key: &str, value: &str, no_keyring: bool) -> Result<(), Box<dyn
     Ok(())
 }
 
-fn keyring_load(key: &str, no_keyring: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+fn keyring_load(profile: &str, key: &str, no_keyring: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let key = &profile_key(profile, key);
     if no_keyring {
         return load_secret_from_file(key);
     }
@@ -97,7 +108,8 @@ fn keyring_load(key: &str, no_keyring: bool) -> Result<Option<String>, Box<dyn s
     }
 }
 
-fn keyring_delete(key: &str, no_keyring: bool) {
+fn keyring_delete(profile: &str, key: &str, no_keyring: bool) {
+    let key = &profile_key(profile, key);
     if no_keyring {
         delete_secret_from_file(key);
         return;
@@ -166,6 +178,7 @@ async fn authenticate_browser(
     oidc_config: &OidcConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let no_keyring = context.args.no_keyring;
+    let profile = context.args.profile.clone();
 
     // Generate PKCE code_verifier (64 URL-safe chars)
     let code_verifier: String = rand::thread_rng()
@@ -247,10 +260,10 @@ async fn authenticate_browser(
         + expires_in;
 
     let cached = CachedTokenJson { token: token_resp.access_token.clone(), until };
-    keyring_store("browser_access_token", &serde_json::to_string(&cached)?, no_keyring)?;
+    keyring_store(&profile, "browser_access_token", &serde_json::to_string(&cached)?, no_keyring)?;
 
     if let Some(refresh) = &token_resp.refresh_token {
-        keyring_store("browser_refresh_token", refresh, no_keyring)?;
+        keyring_store(&profile, "browser_refresh_token", refresh, no_keyring)?;
     }
 
     context.auth_token = Some(CachedToken { token: token_resp.access_token, until });
@@ -262,8 +275,9 @@ async fn refresh_browser_token(
     oidc_config: &OidcConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let no_keyring = context.args.no_keyring;
+    let profile = context.args.profile.clone();
 
-    let refresh_token = keyring_load("browser_refresh_token", no_keyring)?
+    let refresh_token = keyring_load(&profile, "browser_refresh_token", no_keyring)?
         .ok_or("Session expired. Please run 'fb auth' to log in again.")?;
 
     let mut params = HashMap::new();
@@ -299,10 +313,10 @@ async fn refresh_browser_token(
         + expires_in;
 
     let cached = CachedTokenJson { token: token_resp.access_token.clone(), until };
-    keyring_store("browser_access_token", &serde_json::to_string(&cached)?, no_keyring)?;
+    keyring_store(&profile, "browser_access_token", &serde_json::to_string(&cached)?, no_keyring)?;
 
     if let Some(new_refresh) = &token_resp.refresh_token {
-        keyring_store("browser_refresh_token", new_refresh, no_keyring)?;
+        keyring_store(&profile, "browser_refresh_token", new_refresh, no_keyring)?;
     }
 
     context.auth_token = Some(CachedToken { token: token_resp.access_token, until });
@@ -314,9 +328,10 @@ async fn authenticate_browser_from_keyring(
     oauth_env: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let no_keyring = context.args.no_keyring;
+    let profile = context.args.profile.clone();
 
     // Try cached access token first
-    if let Some(token_json) = keyring_load("browser_access_token", no_keyring)? {
+    if let Some(token_json) = keyring_load(&profile, "browser_access_token", no_keyring)? {
         if let Ok(cached) = serde_json::from_str::<CachedTokenJson>(&token_json) {
             let valid_until =
                 SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(cached.until);
@@ -330,7 +345,7 @@ async fn authenticate_browser_from_keyring(
     }
 
     // Token expired or missing — check refresh token before attempting refresh
-    if keyring_load("browser_refresh_token", no_keyring)?.is_none() {
+    if keyring_load(&profile, "browser_refresh_token", no_keyring)?.is_none() {
         return Err("Session expired. Please run 'fb auth' to log in again.".into());
     }
 
@@ -344,6 +359,7 @@ pub async fn authenticate_service_account(
     context: &mut Context,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let no_keyring = context.args.no_keyring;
+    let profile = context.args.profile.clone();
 
     // Check in-memory token
     if let Some(token) = &context.auth_token {
@@ -371,7 +387,7 @@ pub async fn authenticate_service_account(
     let sa_id = args.sa_id.clone();
 
     // Check keyring cache
-    if let Some(token_json) = keyring_load("sa_access_token", no_keyring)? {
+    if let Some(token_json) = keyring_load(&profile, "sa_access_token", no_keyring)? {
         if let Ok(cached) = serde_json::from_str::<CachedTokenJson>(&token_json) {
             let valid_until =
                 SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(cached.until);
@@ -453,7 +469,7 @@ pub async fn authenticate_service_account(
 
             // Cache in keyring
             let cached_json = CachedTokenJson { token: token_str.clone(), until };
-            keyring_store("sa_access_token", &serde_json::to_string(&cached_json)?, no_keyring)?;
+            keyring_store(&profile, "sa_access_token", &serde_json::to_string(&cached_json)?, no_keyring)?;
 
             if context.args.verbose {
                 eprintln!(
@@ -487,7 +503,7 @@ pub async fn maybe_authenticate(
     }
 
     // Check saved credentials for auth method
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(&context.args.profile)?;
     if !creds_path.exists() {
         return Ok(());
     }
@@ -634,13 +650,14 @@ async fn discover_system_engine_url(
 // ─── Create context from saved credentials (used internally) ─────────────────
 
 pub async fn create_context_from_credentials(
+    profile: &str,
     host: String,
     database: String,
     format: String,
     no_spinner: bool,
     no_keyring: bool,
 ) -> Result<Context, Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     if !creds_path.exists() {
         return Err("No saved credentials found. Run 'fb auth' first.".into());
     }
@@ -654,7 +671,7 @@ pub async fn create_context_from_credentials(
 
     if let AuthMethod::ServiceAccount { sa_id: id } = &saved_creds.auth_method {
         sa_id = id.clone();
-        if let Some(secret) = keyring_load("sa_secret", no_keyring)? {
+        if let Some(secret) = keyring_load(profile, "sa_secret", no_keyring)? {
             sa_secret = secret;
         }
     }
@@ -678,6 +695,27 @@ pub async fn create_context_from_credentials(
         hide_pii: false,
         no_spinner,
         no_keyring,
+        timing: String::new(),
+        stats_json: String::new(),
+        read_only: false,
+        profile: profile.to_string(),
+        pset: vec![],
+        expanded: String::new(),
+        max_result_memory: String::from("0"),
+        max_rows: 0,
+        max_bytes: String::from("0"),
+        confirm_bytes: String::from("0"),
+        template: String::new(),
+        template_file: String::new(),
+        plot: String::new(),
+        summarize: false,
+        auto_start_engine: false,
+        wait: false,
+        wait_timeout: 0,
+        engine_type: String::new(),
+        nodes: 0,
+        clusters: 0,
+        description: String::new(),
         update_defaults: false,
         version: false,
         help: false,
@@ -694,6 +732,7 @@ pub async fn create_context_from_credentials(
 // ─── Interactive setup ────────────────────────────────────────────────────────
 
 pub async fn interactive_auth_setup(
+    profile: &str,
     no_keyring: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("Welcome to Firebolt CLI authentication setup!\n");
@@ -712,13 +751,14 @@ pub async fn interactive_auth_setup(
     let api_endpoint = "api.app.firebolt.io";
 
     if choice == "2" {
-        setup_service_account(no_keyring, oauth_env, api_endpoint).await
+        setup_service_account(profile, no_keyring, oauth_env, api_endpoint).await
     } else {
-        setup_browser(no_keyring, oauth_env, api_endpoint).await
+        setup_browser(profile, no_keyring, oauth_env, api_endpoint).await
     }
 }
 
 async fn setup_browser(
+    profile: &str,
     no_keyring: bool,
     oauth_env: &str,
     api_endpoint: &str,
@@ -744,6 +784,27 @@ async fn setup_browser(
         hide_pii: false,
         no_spinner: true,
         no_keyring,
+        timing: String::new(),
+        stats_json: String::new(),
+        read_only: false,
+        profile: profile.to_string(),
+        pset: vec![],
+        expanded: String::new(),
+        max_result_memory: String::from("0"),
+        max_rows: 0,
+        max_bytes: String::from("0"),
+        confirm_bytes: String::from("0"),
+        template: String::new(),
+        template_file: String::new(),
+        plot: String::new(),
+        summarize: false,
+        auto_start_engine: false,
+        wait: false,
+        wait_timeout: 0,
+        engine_type: String::new(),
+        nodes: 0,
+        clusters: 0,
+        description: String::new(),
         update_defaults: false,
         version: false,
         help: false,
@@ -781,9 +842,10 @@ async fn setup_browser(
         account_name,
         host: final_host,
         database: final_database.clone(),
+        read_only: false,
     };
 
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     fs::write(&creds_path, serde_yaml::to_string(&saved_creds)?)?;
 
     println!("\nCredentials saved to {:?}", creds_path);
@@ -798,6 +860,7 @@ async fn setup_browser(
 }
 
 async fn setup_service_account(
+    profile: &str,
     no_keyring: bool,
     oauth_env: &str,
     api_endpoint: &str,
@@ -821,12 +884,10 @@ async fn setup_service_account(
                     eprintln!();
                     break;
                 }
-                console::Key::Backspace => {
-                    if !sa_secret.is_empty() {
-                        sa_secret.pop();
-                        eprint!("\x08 \x08");
-                        io::stderr().flush()?;
-                    }
+                console::Key::Backspace if !sa_secret.is_empty() => {
+                    sa_secret.pop();
+                    eprint!("\x08 \x08");
+                    io::stderr().flush()?;
                 }
                 console::Key::Char(c) if !c.is_control() => {
                     sa_secret.push(c);
@@ -860,6 +921,27 @@ async fn setup_service_account(
         hide_pii: false,
         no_spinner: true,
         no_keyring,
+        timing: String::new(),
+        stats_json: String::new(),
+        read_only: false,
+        profile: profile.to_string(),
+        pset: vec![],
+        expanded: String::new(),
+        max_result_memory: String::from("0"),
+        max_rows: 0,
+        max_bytes: String::from("0"),
+        confirm_bytes: String::from("0"),
+        template: String::new(),
+        template_file: String::new(),
+        plot: String::new(),
+        summarize: false,
+        auto_start_engine: false,
+        wait: false,
+        wait_timeout: 0,
+        engine_type: String::new(),
+        nodes: 0,
+        clusters: 0,
+        description: String::new(),
         update_defaults: false,
         version: false,
         help: false,
@@ -878,7 +960,7 @@ async fn setup_service_account(
     println!("✓ Authentication successful!");
 
     // Store SA secret in keyring
-    keyring_store("sa_secret", &sa_secret, no_keyring)?;
+    keyring_store(profile, "sa_secret", &sa_secret, no_keyring)?;
 
     let account_name = select_account_interactive(&access_token, api_endpoint).await?;
     temp_context.args.account_name = account_name.clone();
@@ -900,9 +982,10 @@ async fn setup_service_account(
         account_name,
         host: final_host,
         database: final_database.clone(),
+        read_only: false,
     };
 
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     fs::write(&creds_path, serde_yaml::to_string(&saved_creds)?)?;
 
     println!("\nCredentials saved to {:?}", creds_path);
@@ -1030,10 +1113,11 @@ async fn execute_query_internal(
 // ─── Set default database / engine ───────────────────────────────────────────
 
 pub async fn set_default_database(
+    profile: &str,
     database_name: String,
     no_keyring: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     let saved_creds: SavedCredentials =
         serde_yaml::from_str(&fs::read_to_string(&creds_path)?)
             .map_err(|_| "No valid authentication session found. Run 'fb auth' to set up.")?;
@@ -1045,6 +1129,7 @@ pub async fn set_default_database(
     };
 
     let mut temp_context = create_context_from_credentials(
+        profile,
         system_engine_host,
         String::new(),
         String::from("TabSeparatedWithNames"),
@@ -1075,11 +1160,33 @@ pub async fn set_default_database(
     }
 }
 
+// Turn read-only mode of the current profile on or off.
+pub fn set_read_only(profile: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let read_only = match value.to_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => true,
+        "off" | "false" | "no" | "0" => false,
+        _ => return Err(format!("Invalid read-only value '{}': expected on or off", value).into()),
+    };
+
+    let creds_path = credentials_path(profile)?;
+    let mut saved_creds: SavedCredentials = fs::read_to_string(&creds_path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str(&content).ok())
+        .ok_or("No valid authentication session found. Run 'fb auth' to set up.")?;
+    saved_creds.read_only = read_only;
+    fs::write(&creds_path, serde_yaml::to_string(&saved_creds)?)?;
+
+    let profile = if profile.is_empty() { "default" } else { profile };
+    println!("✓ Read-only mode {} for profile '{}'", if read_only { "enabled" } else { "disabled" }, profile);
+    Ok(())
+}
+
 pub async fn set_default_engine(
+    profile: &str,
     engine_name: String,
     no_keyring: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     if !creds_path.exists() {
         return Err("No saved credentials found. Run 'fb auth' first.".into());
     }
@@ -1089,12 +1196,13 @@ pub async fn set_default_engine(
             .map_err(|_| "No valid authentication session found. Run 'fb auth' to set up.")?;
 
     let system_engine_host = if let Some(host) = &saved_creds.host {
-        if let Some(pos) = host.find("?engine=") { host[..pos].to_string() } else { host.clone() }
+        system_engine_host(host)
     } else {
         return Err("No host configured. Run 'fb auth' first.".into());
     };
 
     let mut temp_context = create_context_from_credentials(
+        profile,
         system_engine_host,
         saved_creds.database.clone().unwrap_or_default(),
         String::new(),
@@ -1104,35 +1212,62 @@ pub async fn set_default_engine(
     .await?;
 
     println!("Validating engine '{}'...", engine_name);
+    if !engine_exists(&mut temp_context, &engine_name).await {
+        eprintln!("Engine '{}' does not exist.", engine_name);
+        eprintln!("Run 'fb show engines' to see available engines.");
+        return Err("Engine validation failed".into());
+    }
+
+    println!("Resolving engine '{}' endpoint...", engine_name);
+    let host = resolve_engine_endpoint(&mut temp_context, &engine_name).await?;
+
+    saved_creds.host = Some(host.clone());
+    fs::write(&creds_path, serde_yaml::to_string(&saved_creds)?)?;
+    println!("✓ Default engine set to: {} ({})", engine_name, host);
+
+    Ok(())
+}
+
+/// Strip any ?engine= query param from a host to get the system engine host.
+pub fn system_engine_host(host: &str) -> String {
+    if let Some(pos) = host.find("?engine=") {
+        host[..pos].to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+/// Check that an engine exists, using a context connected to the system engine.
+pub async fn engine_exists(system_context: &mut Context, engine_name: &str) -> bool {
     let check_query = format!(
         "SELECT engine_name FROM information_schema.engines WHERE engine_name = '{}'",
         engine_name.replace('\'', "''")
     );
 
-    match execute_query_internal(&mut temp_context, check_query).await {
-        Ok(response) if response.contains(&engine_name) => {}
-        _ => {
-            eprintln!("Engine '{}' does not exist.", engine_name);
-            eprintln!("Run 'fb show engines' to see available engines.");
-            return Err("Engine validation failed".into());
-        }
+    matches!(execute_query_internal(system_context, check_query).await, Ok(response) if response.contains(engine_name))
+}
+
+/// Resolve the endpoint of an engine through USE ENGINE on the system engine, returns the new host.
+pub async fn resolve_engine_endpoint(
+    system_context: &mut Context,
+    engine_name: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // A stopped engine is started first when allowed, so that queries on it do not fail
+    let stopped = crate::engine::engine_status(system_context, engine_name).await.is_ok_and(|s| s.eq_ignore_ascii_case("STOPPED"));
+    if stopped && crate::engine::offer_start(system_context, engine_name, "connect to it")? {
+        crate::engine::start_engine(system_context, engine_name).await?;
     }
 
-    println!("Resolving engine '{}' endpoint...", engine_name);
     let use_engine_query = format!("USE ENGINE {}", engine_name);
-    crate::query::query(&mut temp_context, use_engine_query).await?;
+    crate::query::query(system_context, use_engine_query).await?;
 
-    saved_creds.host = Some(temp_context.args.host.clone());
-    fs::write(&creds_path, serde_yaml::to_string(&saved_creds)?)?;
-    println!("✓ Default engine set to: {} ({})", engine_name, temp_context.args.host);
-
-    Ok(())
+    Ok(system_context.args.host.clone())
 }
 
 // ─── Auth status, clear, token ───────────────────────────────────────────────
 
-pub fn show_auth_status(no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+pub fn show_auth_status(profile: &str, no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let creds_path = credentials_path(profile)?;
     if !creds_path.exists() {
         println!("No saved credentials found.");
         println!("Run 'fb auth' to set up authentication.");
@@ -1165,7 +1300,7 @@ pub fn show_auth_status(no_keyring: bool) -> Result<(), Box<dyn std::error::Erro
         AuthMethod::Browser => "browser_access_token",
         AuthMethod::ServiceAccount { .. } => "sa_access_token",
     };
-    if let Ok(Some(token_json)) = keyring_load(token_key, no_keyring) {
+    if let Ok(Some(token_json)) = keyring_load(profile, token_key, no_keyring) {
         if let Ok(cached) = serde_json::from_str::<CachedTokenJson>(&token_json) {
             let valid_until =
                 SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(cached.until);
@@ -1186,12 +1321,15 @@ pub fn show_auth_status(no_keyring: bool) -> Result<(), Box<dyn std::error::Erro
     if let Some(database) = &saved_creds.database {
         println!("  Default database: {}", database);
     }
+    if saved_creds.read_only {
+        println!("  Read-only: yes");
+    }
 
     Ok(())
 }
 
-pub fn clear_auth(no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+pub fn clear_auth(profile: &str, no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let creds_path = credentials_path(profile)?;
     if creds_path.exists() {
         fs::remove_file(&creds_path)?;
         println!("Credentials cleared from {:?}", creds_path);
@@ -1200,21 +1338,22 @@ pub fn clear_auth(no_keyring: bool) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Clear keyring / secrets file
-    keyring_delete("sa_secret", no_keyring);
-    keyring_delete("sa_access_token", no_keyring);
-    keyring_delete("browser_access_token", no_keyring);
-    keyring_delete("browser_refresh_token", no_keyring);
+    keyring_delete(profile, "sa_secret", no_keyring);
+    keyring_delete(profile, "sa_access_token", no_keyring);
+    keyring_delete(profile, "browser_access_token", no_keyring);
+    keyring_delete(profile, "browser_refresh_token", no_keyring);
     // Legacy keys (migration cleanup)
-    keyring_delete("access_token", no_keyring);
-    keyring_delete("refresh_token", no_keyring);
+    keyring_delete(profile, "access_token", no_keyring);
+    keyring_delete(profile, "refresh_token", no_keyring);
 
     Ok(())
 }
 
 pub async fn print_access_token(
+    profile: &str,
     no_keyring: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     if !creds_path.exists() {
         eprintln!("No saved credentials found. Run 'fb auth' first.");
         std::process::exit(1);
@@ -1231,6 +1370,7 @@ pub async fn print_access_token(
     // Build a minimal context to use the auth machinery
     let host = saved_creds.host.clone().unwrap_or_default();
     let context = create_context_from_credentials(
+        profile,
         host,
         String::new(),
         String::new(),
@@ -1254,7 +1394,7 @@ pub async fn print_access_token(
         AuthMethod::Browser => "browser_access_token",
         AuthMethod::ServiceAccount { .. } => "sa_access_token",
     };
-    if let Some(token_json) = keyring_load(token_key, no_keyring)? {
+    if let Some(token_json) = keyring_load(profile, token_key, no_keyring)? {
         if let Ok(cached) = serde_json::from_str::<CachedTokenJson>(&token_json) {
             print!("{}", cached.token);
             return Ok(());
@@ -1270,7 +1410,7 @@ pub async fn print_access_token(
 pub fn load_saved_credentials(
     args: &mut crate::args::Args,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(&args.profile)?;
     if !creds_path.exists() {
         return Ok(());
     }
@@ -1295,6 +1435,7 @@ pub fn load_saved_credentials(
 
     args.oauth_env = saved_creds.oauth_env.clone();
     args.account_name = saved_creds.account_name.clone();
+    args.read_only = args.read_only || saved_creds.read_only;
 
     match &saved_creds.auth_method {
         AuthMethod::ServiceAccount { sa_id } => {
@@ -1303,7 +1444,7 @@ pub fn load_saved_credentials(
             }
             // Load SA secret from keyring if not provided on CLI
             if args.sa_secret.is_empty() {
-                if let Ok(Some(secret)) = keyring_load("sa_secret", args.no_keyring) {
+                if let Ok(Some(secret)) = keyring_load(&args.profile, "sa_secret", args.no_keyring) {
                     args.sa_secret = secret;
                 }
             }
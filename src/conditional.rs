@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use crate::query::interpolate_variables;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Branch {
+    // The current branch runs
+    Active,
+    // No branch has run yet, a later \elif or \else may run
+    Pending,
+    // A previous branch ran, the rest of the block is skipped
+    Done,
+    // The whole block is inside a skipped branch
+    Ignored,
+}
+
+#[derive(Debug)]
+struct Block {
+    branch: Branch,
+    seen_else: bool,
+}
+
+// Nested \if blocks of the statement loop.
+#[derive(Debug, Default)]
+pub struct ConditionalStack {
+    blocks: Vec<Block>,
+}
+
+// psql-style booleans: true/false, yes/no (or a prefix), on/off, 1/0.
+fn parse_bool(value: &str) -> Option<bool> {
+    let value = value.trim().to_lowercase();
+    match value.as_str() {
+        "" => None,
+        "1" | "on" => Some(true),
+        "0" | "of" | "off" => Some(false),
+        _ if "true".starts_with(&value) || "yes".starts_with(&value) => Some(true),
+        _ if "false".starts_with(&value) || "no".starts_with(&value) => Some(false),
+        _ => None,
+    }
+}
+
+// Evaluate a condition after substituting :variables.
+fn evaluate(keyword: &str, expression: &str, variables: &BTreeMap<String, String>) -> Result<bool, Box<dyn std::error::Error>> {
+    let value = interpolate_variables(expression, variables);
+    parse_bool(&value).ok_or_else(|| format!("Unrecognized value \"{}\" for \\{}: Boolean expected", value.trim(), keyword).into())
+}
+
+impl ConditionalStack {
+    // Whether lines are currently skipped.
+    pub fn is_skipping(&self) -> bool {
+        self.blocks.last().is_some_and(|block| block.branch != Branch::Active)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    // Drop blocks opened beyond `depth`, e.g. by a script that failed.
+    pub fn truncate(&mut self, depth: usize) {
+        self.blocks.truncate(depth);
+    }
+
+    // Apply \if, \elif, \else or \endif. Conditions of skipped branches are not evaluated.
+    pub fn apply(
+        &mut self,
+        keyword: &str,
+        expression: &str,
+        variables: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if keyword != "if" && keyword != "endif" && self.blocks.last().is_some_and(|block| block.seen_else) {
+            return Err(format!("\\{} after \\else", keyword).into());
+        }
+
+        match keyword {
+            "if" => {
+                if self.is_skipping() {
+                    self.blocks.push(Block { branch: Branch::Ignored, seen_else: false });
+                    return Ok(());
+                }
+                // An invalid condition counts as false, so the block is still tracked
+                self.blocks.push(Block { branch: Branch::Pending, seen_else: false });
+                if evaluate(keyword, expression, variables)? {
+                    self.blocks.last_mut().unwrap().branch = Branch::Active;
+                }
+            }
+            "elif" => {
+                let block = self.blocks.last_mut().ok_or("\\elif without matching \\if")?;
+                match block.branch {
+                    Branch::Active => block.branch = Branch::Done,
+                    Branch::Pending => {
+                        if evaluate(keyword, expression, variables)? {
+                            block.branch = Branch::Active;
+                        }
+                    }
+                    Branch::Done | Branch::Ignored => {}
+                }
+            }
+            "else" => {
+                let block = self.blocks.last_mut().ok_or("\\else without matching \\if")?;
+                block.seen_else = true;
+                block.branch = match block.branch {
+                    Branch::Active => Branch::Done,
+                    Branch::Pending => Branch::Active,
+                    branch => branch,
+                };
+            }
+            "endif" => {
+                self.blocks.pop().ok_or("\\endif without matching \\if")?;
+            }
+            _ => return Err(format!("Unknown conditional command: \\{}", keyword).into()),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("true"), Some(true));
+        assert_eq!(parse_bool("t"), Some(true));
+        assert_eq!(parse_bool(" YES "), Some(true));
+        assert_eq!(parse_bool("on"), Some(true));
+        assert_eq!(parse_bool("1"), Some(true));
+        assert_eq!(parse_bool("f"), Some(false));
+        assert_eq!(parse_bool("off"), Some(false));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("o"), None);
+        assert_eq!(parse_bool("maybe"), None);
+        assert_eq!(parse_bool(""), None);
+    }
+
+    #[test]
+    fn test_branches() {
+        let mut variables = BTreeMap::new();
+        variables.insert("exists".to_string(), "t".to_string());
+        let mut stack = ConditionalStack::default();
+
+        stack.apply("if", ":exists", &variables).unwrap();
+        assert!(!stack.is_skipping());
+        stack.apply("elif", "true", &variables).unwrap();
+        assert!(stack.is_skipping());
+        stack.apply("else", "", &variables).unwrap();
+        assert!(stack.is_skipping());
+        stack.apply("endif", "", &variables).unwrap();
+        assert!(!stack.is_skipping());
+
+        stack.apply("if", "false", &variables).unwrap();
+        assert!(stack.is_skipping());
+        stack.apply("elif", "yes", &variables).unwrap();
+        assert!(!stack.is_skipping());
+        stack.apply("endif", "", &variables).unwrap();
+        assert_eq!(stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_nested_skipped_blocks_are_not_evaluated() {
+        let variables = BTreeMap::new();
+        let mut stack = ConditionalStack::default();
+
+        stack.apply("if", "off", &variables).unwrap();
+        // Would be an error if evaluated
+        stack.apply("if", ":undefined", &variables).unwrap();
+        stack.apply("else", "", &variables).unwrap();
+        assert!(stack.is_skipping());
+        stack.apply("endif", "", &variables).unwrap();
+        stack.apply("else", "", &variables).unwrap();
+        assert!(!stack.is_skipping());
+        stack.apply("endif", "", &variables).unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        let variables = BTreeMap::new();
+        let mut stack = ConditionalStack::default();
+
+        assert!(stack.apply("endif", "", &variables).is_err());
+        assert!(stack.apply("else", "", &variables).is_err());
+
+        let error = stack.apply("if", ":undefined", &variables).unwrap_err().to_string();
+        assert!(error.contains("Boolean expected"), "{}", error);
+        // The block is still open and skipped
+        assert!(stack.is_skipping());
+
+        stack.apply("else", "", &variables).unwrap();
+        assert!(stack.apply("elif", "true", &variables).is_err());
+        assert!(stack.apply("else", "", &variables).is_err());
+    }
+}
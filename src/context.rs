@@ -1,5 +1,14 @@
 use crate::args::{get_url, Args};
+use crate::conditional::ConditionalStack;
+use crate::render::PrintOptions;
+use crate::result::ResultSet;
+use crate::template::Template;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "auth_method")]
@@ -17,6 +26,8 @@ pub struct SavedCredentials {
     pub account_name: String,
     pub host: Option<String>,
     pub database: Option<String>,
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -25,6 +36,37 @@ pub struct CachedToken {
     pub until: u64, // expiry as unix epoch seconds
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingSource {
+    Cli,
+    ConfigFile,
+    SetStatement,
+    ServerHeader,
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingSource::Cli => write!(f, "CLI --extra"),
+            SettingSource::ConfigFile => write!(f, "config file"),
+            SettingSource::SetStatement => write!(f, "set statement"),
+            SettingSource::ServerHeader => write!(f, "server header"),
+        }
+    }
+}
+
+// Initial settings are from the config file when it has the same entry, otherwise from the CLI.
+pub fn initial_setting_sources(extra: &[String], config: &[String]) -> BTreeMap<String, SettingSource> {
+    extra
+        .iter()
+        .map(|e| {
+            let key = e.split('=').next().unwrap_or_default().to_string();
+            let source = if config.contains(e) { SettingSource::ConfigFile } else { SettingSource::Cli };
+            (key, source)
+        })
+        .collect()
+}
+
 pub struct Context {
     pub args: Args,
     pub url: String,
@@ -33,12 +75,69 @@ pub struct Context {
     pub prompt1: Option<String>,
     pub prompt2: Option<String>,
     pub prompt3: Option<String>,
+    pub last_query: Option<String>,
+    pub query_buffer: String,
+    pub pending_input: Option<String>,
+    pub include_stack: Vec<PathBuf>,
+    pub initial_extra: Vec<String>,
+    // Extra settings of the config file, see get_args_with_config
+    pub config_extra: Vec<String>,
+    pub setting_sources: BTreeMap<String, SettingSource>,
+    pub variables: BTreeMap<String, String>,
+    pub output_capture: Option<String>,
+    pub output_file: Option<(PathBuf, File)>,
+    pub conditionals: ConditionalStack,
+    pub last_result: Option<ResultSet>,
+    pub print_options: PrintOptions,
+    pub template: Option<Template>,
+    pub stats_file: Option<File>,
+    pub statements_run: usize,
+    // \async was given and the next statement is submitted in the background
+    pub async_next: bool,
+    // Tokens of asynchronous queries whose completion is not reported yet
+    pub async_queries: Vec<String>,
 }
 
 impl Context {
     pub fn new(args: Args) -> Self {
         let url = get_url(&args);
-        Self { args, url, auth_token: None, saved_creds: None, prompt1: None, prompt2: None, prompt3: None }
+        let initial_extra = args.extra.clone();
+        let setting_sources = initial_setting_sources(&initial_extra, &[]);
+        let print_options = PrintOptions::from_settings(&args.pset).unwrap_or_default();
+        let template = Some(&args.template).filter(|t| !t.is_empty()).and_then(|t| Template::parse(t).ok());
+        Self {
+            args,
+            url,
+            auth_token: None,
+            saved_creds: None,
+            prompt1: None,
+            prompt2: None,
+            prompt3: None,
+            last_query: None,
+            query_buffer: String::new(),
+            pending_input: None,
+            include_stack: Vec::new(),
+            initial_extra,
+            config_extra: Vec::new(),
+            setting_sources,
+            variables: BTreeMap::new(),
+            output_capture: None,
+            output_file: None,
+            conditionals: ConditionalStack::default(),
+            last_result: None,
+            print_options,
+            template,
+            stats_file: None,
+            statements_run: 0,
+            async_next: false,
+            async_queries: Vec::new(),
+        }
+    }
+
+    // Tell settings that come from the config file apart from those given on the command line.
+    pub fn set_config_extra(&mut self, config_extra: Vec<String>) {
+        self.setting_sources = initial_setting_sources(&self.initial_extra, &config_extra);
+        self.config_extra = config_extra;
     }
 
     pub fn update_url(&mut self) {
@@ -49,6 +148,25 @@ impl Context {
         self.auth_token.as_ref().map(|t| t.token.as_str())
     }
 
+    // Write query output to the active capture, the \o file or stdout.
+    pub fn write_output(&mut self, text: &str) -> std::io::Result<()> {
+        if let Some(capture) = &mut self.output_capture {
+            capture.push_str(text);
+        } else if let Some((_, file)) = &mut self.output_file {
+            file.write_all(text.as_bytes())?;
+        } else {
+            // on stdout, on purpose
+            print!("{}", text);
+        }
+        Ok(())
+    }
+
+    // Remember where a setting, given as "key" or "key=value", came from.
+    pub fn set_setting_source(&mut self, setting: &str, source: SettingSource) {
+        let key = setting.split('=').next().unwrap_or_default().trim();
+        self.setting_sources.insert(key.to_string(), source);
+    }
+
     pub fn set_prompt1(&mut self, prompt: String) {
         self.prompt1 = Some(prompt);
     }
@@ -78,4 +196,20 @@ mod tests {
         assert!(context.url.contains("database=test_db"));
         assert!(context.auth_token.is_none());
     }
+
+    #[test]
+    fn test_setting_sources() {
+        let mut args = crate::args::get_args().unwrap();
+        args.extra = vec!["engine=my_engine".to_string()];
+
+        let mut context = Context::new(args);
+        assert_eq!(context.setting_sources.get("engine"), Some(&SettingSource::Cli));
+
+        context.set_setting_source("transaction_id=42", SettingSource::ServerHeader);
+        assert_eq!(context.setting_sources.get("transaction_id"), Some(&SettingSource::ServerHeader));
+        assert_eq!(SettingSource::ServerHeader.to_string(), "server header");
+
+        context.set_config_extra(vec!["engine=my_engine".to_string()]);
+        assert_eq!(context.setting_sources.get("engine"), Some(&SettingSource::ConfigFile));
+    }
 }
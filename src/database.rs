@@ -0,0 +1,137 @@
+use crate::args::Args;
+use crate::context::Context;
+use crate::meta_commands::{parse_single_row, Row};
+use crate::query::{capture_internal, query};
+use crate::show::create_query_context;
+use crate::utils::{format_size, sql_identifier, sql_literal};
+
+// Tables and views of a database, without the information_schema views.
+const TABLES_QUERY: &str = "SELECT table_schema, table_name, table_type, table_owner, number_of_rows, compressed_bytes \
+                            FROM information_schema.tables WHERE table_schema <> 'information_schema' \
+                            ORDER BY table_schema, table_name";
+const TOTALS_QUERY: &str = "SELECT SUM(CASE WHEN table_type = 'VIEW' THEN 0 ELSE 1 END) AS tables, \
+                            SUM(CASE WHEN table_type = 'VIEW' THEN 1 ELSE 0 END) AS views, \
+                            SUM(compressed_bytes) AS compressed_bytes \
+                            FROM information_schema.tables WHERE table_schema <> 'information_schema'";
+
+fn create_statement(name: &str, description: &str) -> String {
+    if description.is_empty() {
+        format!("CREATE DATABASE {}", sql_identifier(name))
+    } else {
+        format!("CREATE DATABASE {} WITH DESCRIPTION = {}", sql_identifier(name), sql_literal(description))
+    }
+}
+
+fn field<'a>(row: &'a Row, name: &str) -> Option<&'a str> {
+    row.iter().find(|(n, _)| n == name).and_then(|(_, v)| v.as_deref()).filter(|v| !v.is_empty())
+}
+
+// Header lines of fb database describe.
+fn format_summary(name: &str, catalog: &Row, totals: &Row) -> String {
+    let count = |name: &str| field(totals, name).unwrap_or("0").to_string();
+    let size = field(totals, "compressed_bytes").and_then(|b| b.parse().ok()).unwrap_or(0);
+
+    let mut lines = vec![format!("Database: {}", name)];
+    lines.push(format!("Owner: {}", field(catalog, "catalog_owner").unwrap_or("(unknown)")));
+    if let Some(description) = field(catalog, "description") {
+        lines.push(format!("Description: {}", description));
+    }
+    if let Some(created) = field(catalog, "created") {
+        lines.push(format!("Created: {}", created));
+    }
+    lines.push(format!("Tables: {}, views: {}", count("tables"), count("views")));
+    lines.push(format!("Total size: {} (compressed)", format_size(size)));
+    lines.join("\n") + "\n"
+}
+
+// Show owner, size, tables and views of a database.
+async fn describe_database(args: &Args, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut context = create_query_context(&args.profile, None, None).await?;
+    let lookup = format!(
+        "SELECT catalog_name, catalog_owner, description, created FROM information_schema.catalogs WHERE catalog_name = {}",
+        sql_literal(name)
+    );
+    let catalog = parse_single_row(&capture_internal(&mut context, lookup).await?).map_err(|e| {
+        if e.to_string().contains("no rows") {
+            format!("Database '{}' does not exist.", name).into()
+        } else {
+            e
+        }
+    })?;
+
+    // Tables are listed by the information_schema of the database itself
+    let mut context = create_query_context(&args.profile, Some(name.to_string()), None).await?;
+    context.args.concise = args.concise;
+    context.args.no_spinner = args.no_spinner;
+    let totals = parse_single_row(&capture_internal(&mut context, TOTALS_QUERY.to_string()).await?)?;
+
+    print!("{}", format_summary(name, &catalog, &totals));
+    query(&mut context, TABLES_QUERY.to_string()).await
+}
+
+// Drop a database. Like any DROP statement it is refused in read-only mode and confirmed in an interactive terminal.
+async fn drop_database(context: &mut Context, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    query(context, format!("DROP DATABASE {}", sql_identifier(name))).await?;
+    println!("Database '{}' dropped.", name);
+    Ok(())
+}
+
+// Run fb database create|drop|describe on the system engine.
+pub async fn database_command(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let action = args.query[1].as_str();
+    let name = args.query[2].as_str();
+
+    if action == "describe" {
+        return describe_database(args, name).await;
+    }
+
+    let mut context = create_query_context(&args.profile, None, None).await?;
+    context.args.concise = args.concise;
+    context.args.no_spinner = args.no_spinner;
+    context.args.read_only = args.read_only;
+
+    match action {
+        "create" => query(&mut context, create_statement(name, &args.description)).await,
+        "drop" => drop_database(&mut context, name).await,
+        _ => Err(format!("Unknown database command: {}", action).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::get_args;
+
+    fn row(fields: &[(&str, Option<&str>)]) -> Row {
+        fields.iter().map(|(n, v)| (n.to_string(), v.map(str::to_string))).collect()
+    }
+
+    #[test]
+    fn test_create_statement() {
+        assert_eq!(create_statement("sales", ""), "CREATE DATABASE \"sales\"");
+        assert_eq!(create_statement("sales", "Sales data, '24"), "CREATE DATABASE \"sales\" WITH DESCRIPTION = 'Sales data, ''24'");
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let catalog = row(&[("catalog_name", Some("sales")), ("catalog_owner", Some("alice")), ("description", Some(""))]);
+        let totals = row(&[("tables", Some("3")), ("views", Some("1")), ("compressed_bytes", Some("1572864"))]);
+        assert_eq!(
+            format_summary("sales", &catalog, &totals),
+            "Database: sales\nOwner: alice\nTables: 3, views: 1\nTotal size: 1.5MB (compressed)\n"
+        );
+
+        let empty = row(&[("tables", None), ("views", None), ("compressed_bytes", None)]);
+        let summary = format_summary("sales", &row(&[]), &empty);
+        assert!(summary.ends_with("Owner: (unknown)\nTables: 0, views: 0\nTotal size: 0B (compressed)\n"));
+    }
+
+    #[tokio::test]
+    async fn test_drop_read_only() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.read_only = true;
+        // Refused before anything is sent
+        context.args.host = String::from("localhost:59999");
+        assert!(drop_database(&mut context, "sales").await.is_err());
+    }
+}
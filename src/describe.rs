@@ -0,0 +1,177 @@
+use crate::context::Context;
+use crate::query::query;
+use crate::utils::sql_literal;
+
+// Identifiers are case-folded unless double-quoted, like in SQL.
+fn normalize_identifier(name: &str) -> String {
+    if name.len() >= 2 && name.starts_with('"') && name.ends_with('"') {
+        name[1..name.len() - 1].replace("\"\"", "\"")
+    } else {
+        name.to_lowercase()
+    }
+}
+
+// Split "schema.name" into its parts, ignoring dots inside quotes.
+fn split_qualified(name: &str) -> (Option<String>, String) {
+    let mut in_quotes = false;
+    for (i, c) in name.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '.' if !in_quotes => return (Some(normalize_identifier(&name[..i])), normalize_identifier(&name[i + 1..])),
+            _ => {}
+        }
+    }
+
+    (None, normalize_identifier(name))
+}
+
+// Translate psql-style wildcards (* and ?) into a LIKE pattern; literal _ and % are escaped with a backslash.
+fn like_pattern(pattern: &str) -> String {
+    pattern.replace('\\', "\\\\").replace('_', "\\_").replace('%', "\\%").replace('*', "%").replace('?', "_")
+}
+
+// Build filter conditions for an optional "[schema.]name" pattern.
+fn pattern_conditions(schema_column: Option<&str>, name_column: &str, pattern: Option<&str>) -> String {
+    let Some(pattern) = pattern else {
+        return String::new();
+    };
+
+    let (schema, name) = split_qualified(pattern);
+    let mut conditions = String::new();
+    if let (Some(schema_column), Some(schema)) = (schema_column, schema) {
+        conditions += &format!(" AND {} LIKE {} ESCAPE '\\'", schema_column, sql_literal(&like_pattern(&schema)));
+    }
+    conditions += &format!(" AND {} LIKE {} ESCAPE '\\'", name_column, sql_literal(&like_pattern(&name)));
+    conditions
+}
+
+// Build information_schema queries for a \d-family command.
+fn describe_queries(command: &str, verbose: bool, argument: Option<&str>) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let queries = match command {
+        "d" if argument.is_some() => {
+            let (schema, table) = split_qualified(argument.unwrap_or_default());
+            let mut filter = format!("table_name = {}", sql_literal(&table));
+            if let Some(schema) = schema {
+                filter += &format!(" AND table_schema = {}", sql_literal(&schema));
+            }
+
+            let mut queries = vec![format!(
+                "SELECT column_name AS \"column\", data_type AS \"type\", is_nullable AS \"nullable\", \
+                 is_in_primary_index AS \"primary_index\", is_in_partition_expr AS \"partitioning\" \
+                 FROM information_schema.columns WHERE {filter} ORDER BY ordinal_position"
+            )];
+            if verbose {
+                queries.push(format!(
+                    "SELECT table_type AS \"type\", number_of_rows AS \"rows\", compressed_bytes AS \"compressed_bytes\", \
+                     uncompressed_bytes AS \"uncompressed_bytes\" FROM information_schema.tables WHERE {filter}"
+                ));
+            }
+            queries
+        }
+        "d" => vec![String::from(
+            "SELECT table_schema AS \"schema\", table_name AS \"name\", table_type AS \"type\" \
+             FROM information_schema.tables WHERE table_schema <> 'information_schema' ORDER BY 1, 2",
+        )],
+        "dt" => {
+            let sizes = if verbose {
+                ", number_of_rows AS \"rows\", compressed_bytes AS \"compressed_bytes\", uncompressed_bytes AS \"uncompressed_bytes\""
+            } else {
+                ""
+            };
+            vec![format!(
+                "SELECT table_schema AS \"schema\", table_name AS \"name\", table_type AS \"type\"{sizes} \
+                 FROM information_schema.tables WHERE table_type <> 'VIEW' AND table_schema <> 'information_schema'{} \
+                 ORDER BY 1, 2",
+                pattern_conditions(Some("table_schema"), "table_name", argument)
+            )]
+        }
+        "dv" => {
+            let definition = if verbose { ", view_definition AS \"definition\"" } else { "" };
+            vec![format!(
+                "SELECT table_schema AS \"schema\", table_name AS \"name\"{definition} \
+                 FROM information_schema.views WHERE table_schema <> 'information_schema'{} ORDER BY 1, 2",
+                pattern_conditions(Some("table_schema"), "table_name", argument)
+            )]
+        }
+        "di" => vec![format!(
+            "SELECT table_name AS \"table\", index_name AS \"index\", index_type AS \"type\", \
+             index_definition AS \"definition\" FROM information_schema.indexes WHERE 1 = 1{} ORDER BY 1, 2",
+            pattern_conditions(None, "table_name", argument)
+        )],
+        "l" => vec![format!(
+            "SELECT catalog_name AS \"database\" FROM information_schema.catalogs WHERE 1 = 1{} ORDER BY 1",
+            pattern_conditions(None, "catalog_name", argument)
+        )],
+        "de" => vec![format!(
+            "SELECT engine_name AS \"engine\", status, type, nodes, clusters \
+             FROM information_schema.engines WHERE 1 = 1{} ORDER BY 1",
+            pattern_conditions(None, "engine_name", argument)
+        )],
+        _ => return Err(format!("Unknown describe command: \\{}", command).into()),
+    };
+
+    Ok(queries)
+}
+
+// Run a \d-family command (\d, \dt, \dv, \di, \l, \de) in the current context and format.
+pub async fn describe(
+    context: &mut Context,
+    command: &str,
+    verbose: bool,
+    argument: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for query_text in describe_queries(command, verbose, argument)? {
+        query(context, query_text).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(split_qualified("Orders"), (None, "orders".to_string()));
+        assert_eq!(split_qualified("public.Orders"), (Some("public".to_string()), "orders".to_string()));
+        assert_eq!(split_qualified(r#""My.Schema"."Orders""#), (Some("My.Schema".to_string()), "Orders".to_string()));
+        assert_eq!(like_pattern("ord*?"), "ord%_");
+        assert_eq!(like_pattern("my_table"), "my\\_table");
+        assert_eq!(like_pattern("100%*"), "100\\%%");
+    }
+
+    #[test]
+    fn test_describe_table() {
+        let queries = describe_queries("d", false, Some("public.orders")).unwrap();
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].contains("FROM information_schema.columns"));
+        assert!(queries[0].contains("table_name = 'orders' AND table_schema = 'public'"));
+
+        let queries = describe_queries("d", true, Some("orders")).unwrap();
+        assert_eq!(queries.len(), 2);
+        assert!(queries[1].contains("number_of_rows"));
+    }
+
+    #[test]
+    fn test_list_commands() {
+        let queries = describe_queries("dt", false, None).unwrap();
+        assert!(queries[0].contains("FROM information_schema.tables"));
+        assert!(!queries[0].contains("LIKE"));
+
+        let queries = describe_queries("dt", true, Some("ord*")).unwrap();
+        assert!(queries[0].contains("compressed_bytes"));
+        assert!(queries[0].contains("table_name LIKE 'ord%' ESCAPE '\\'"));
+
+        let queries = describe_queries("di", false, Some("orders")).unwrap();
+        assert!(queries[0].contains("FROM information_schema.indexes"));
+
+        let queries = describe_queries("l", false, None).unwrap();
+        assert!(queries[0].contains("FROM information_schema.catalogs"));
+
+        let queries = describe_queries("de", false, Some("my_engine")).unwrap();
+        assert!(queries[0].contains("engine_name LIKE 'my\\_engine' ESCAPE '\\'"));
+
+        assert!(describe_queries("dx", false, None).is_err());
+    }
+}
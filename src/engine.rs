@@ -0,0 +1,306 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio::{select, signal, task};
+use tokio_util::sync::CancellationToken;
+
+use crate::args::Args;
+use crate::context::Context;
+use crate::meta_commands::{parse_single_row, Row};
+use crate::query::{capture_internal, query};
+use crate::session::{current_engine, system_context};
+use crate::show::create_query_context;
+use crate::utils::{confirm, is_interactive, spin, sql_identifier, sql_literal};
+
+// Time between status checks of --wait.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Seconds --wait gives up after unless --wait-timeout is set.
+const DEFAULT_WAIT_TIMEOUT: u64 = 600;
+
+// Size of an engine. Unset values are empty or 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EngineConfig {
+    pub engine_type: String,
+    pub nodes: usize,
+    pub clusters: usize,
+}
+
+impl EngineConfig {
+    // The type is spliced into the statement unquoted, so only names like S or XL are accepted.
+    fn from_args(args: &Args) -> Result<Self, Box<dyn std::error::Error>> {
+        static TYPE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9_]+$").unwrap());
+        if !args.engine_type.is_empty() && !TYPE_RE.is_match(&args.engine_type) {
+            return Err(format!("Invalid engine type '{}': expected a name such as S, M, L or XL", args.engine_type).into());
+        }
+        Ok(Self { engine_type: args.engine_type.to_uppercase(), nodes: args.nodes, clusters: args.clusters })
+    }
+
+    fn from_row(row: &Row) -> Self {
+        let field = |name: &str| row.iter().find(|(n, _)| n == name).and_then(|(_, v)| v.clone()).unwrap_or_default();
+        Self {
+            engine_type: field("type"),
+            nodes: field("nodes").parse().unwrap_or_default(),
+            clusters: field("clusters").parse().unwrap_or_default(),
+        }
+    }
+
+    // Options of CREATE ENGINE ... WITH and ALTER ENGINE ... SET, e.g. TYPE = M NODES = 2
+    fn options(&self) -> String {
+        let mut options = vec![];
+        if !self.engine_type.is_empty() {
+            options.push(format!("TYPE = {}", self.engine_type));
+        }
+        if self.nodes > 0 {
+            options.push(format!("NODES = {}", self.nodes));
+        }
+        if self.clusters > 0 {
+            options.push(format!("CLUSTERS = {}", self.clusters));
+        }
+        options.join(" ")
+    }
+
+    // Configuration with the set values of `changes` applied.
+    fn apply(&self, changes: &EngineConfig) -> EngineConfig {
+        EngineConfig {
+            engine_type: if changes.engine_type.is_empty() { self.engine_type.clone() } else { changes.engine_type.clone() },
+            nodes: if changes.nodes > 0 { changes.nodes } else { self.nodes },
+            clusters: if changes.clusters > 0 { changes.clusters } else { self.clusters },
+        }
+    }
+}
+
+impl fmt::Display for EngineConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        let (nodes, clusters) = (self.nodes, self.clusters);
+        write!(f, "type {}, {} node{}, {} cluster{}", self.engine_type, nodes, plural(nodes), clusters, plural(clusters))
+    }
+}
+
+// SQL run on the system engine for an fb engine action.
+fn engine_statement(action: &str, name: &str, config: &EngineConfig) -> Result<String, Box<dyn std::error::Error>> {
+    let name = sql_identifier(name);
+    let options = config.options();
+    Ok(match action {
+        "start" => format!("START ENGINE {}", name),
+        "stop" => format!("STOP ENGINE {}", name),
+        "drop" => format!("DROP ENGINE {}", name),
+        "create" if options.is_empty() => format!("CREATE ENGINE {}", name),
+        "create" => format!("CREATE ENGINE {} WITH {}", name, options),
+        "resize" if options.is_empty() => return Err("fb engine resize needs --type, --nodes or --clusters".into()),
+        "resize" => format!("ALTER ENGINE {} SET {}", name, options),
+        _ => return Err(format!("Unknown engine command: {}", action).into()),
+    })
+}
+
+// Status and size of an engine from information_schema.engines.
+async fn engine_row(context: &mut Context, name: &str) -> Result<Row, Box<dyn std::error::Error>> {
+    let lookup = format!(
+        "SELECT status, type, nodes, clusters FROM information_schema.engines WHERE engine_name = {}",
+        sql_literal(name)
+    );
+    parse_single_row(&capture_internal(context, lookup).await?).map_err(|e| {
+        if e.to_string().contains("no rows") {
+            format!("Engine '{}' does not exist.", name).into()
+        } else {
+            e
+        }
+    })
+}
+
+pub async fn engine_status(context: &mut Context, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let row = engine_row(context, name).await?;
+    Ok(row.into_iter().find(|(n, _)| n == "status").and_then(|(_, v)| v).unwrap_or_default())
+}
+
+// Poll information_schema.engines until the engine has the target status, showing a spinner.
+async fn wait_for_status(context: &mut Context, name: &str, target: &str, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    let finish_token = CancellationToken::new();
+    let maybe_spin = if context.args.no_spinner || context.args.concise {
+        None
+    } else {
+        let token_clone = finish_token.clone();
+        Some(task::spawn(async {
+            spin(token_clone).await;
+        }))
+    };
+
+    let start = Instant::now();
+    let result = loop {
+        let status = match engine_status(context, name).await {
+            Ok(status) => status,
+            Err(e) => break Err(e),
+        };
+        if status.eq_ignore_ascii_case(target) {
+            break Ok(());
+        }
+        if start.elapsed() >= timeout {
+            break Err(format!("Engine '{}' did not reach {} within {}s (status: {})", name, target, timeout.as_secs(), status).into());
+        }
+
+        select! {
+            _ = signal::ctrl_c() => break Err(format!("Stopped waiting; engine '{}' is {}", name, status).into()),
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    };
+
+    finish_token.cancel();
+    if let Some(spin) = maybe_spin {
+        spin.await?;
+    }
+    result
+}
+
+// Engine named by an "engine is not running" error, or the session engine when the error names none.
+pub fn stopped_engine(context: &Context, body: &str) -> Option<String> {
+    static STOPPED_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(concat!(
+            r#"(?i)\bengine\s+(?:'([^']+)'|\\?"([^"\\]+)\\?"|([\w-]+))?"#,
+            r#"\s*(?:is|was|has been)\s+(?:not running|stopped|not started)"#
+        ))
+        .unwrap()
+    });
+
+    let captures = STOPPED_RE.captures(body)?;
+    let named = captures.get(1).or(captures.get(2)).or(captures.get(3)).map(|m| m.as_str().to_string());
+    named.or_else(|| current_engine(context))
+}
+
+// Whether to start a stopped engine: with --auto-start-engine, or when confirmed in an interactive terminal. `action`
+// names what follows the start in the prompt, e.g. "run the statement".
+pub fn offer_start(context: &Context, name: &str, action: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    if context.args.auto_start_engine {
+        eprintln!("Engine '{}' is not running, starting it...", name);
+        return Ok(true);
+    }
+    if !is_interactive() {
+        return Ok(false);
+    }
+    confirm(&format!("Engine '{}' is not running. Start it and {}? [y/N] ", name, action))
+}
+
+// Start an engine from a context on the system engine and wait until it is running.
+pub async fn start_engine(system_context: &mut Context, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    capture_internal(system_context, format!("START ENGINE {}", sql_identifier(name))).await?;
+
+    let timeout = if system_context.args.wait_timeout > 0 { system_context.args.wait_timeout } else { DEFAULT_WAIT_TIMEOUT };
+    wait_for_status(system_context, name, "RUNNING", Duration::from_secs(timeout)).await?;
+    eprintln!("Engine '{}' is RUNNING.", name);
+    Ok(())
+}
+
+// Start the stopped engine a session statement failed on.
+pub async fn restart_stopped_engine(context: &Context, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut system_context = system_context(context);
+    system_context.args.concise = context.args.concise;
+    start_engine(&mut system_context, name).await
+}
+
+// Run fb engine start|stop|create|drop|resize|status on the system engine.
+pub async fn engine_command(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let action = args.query[1].as_str();
+    let name = args.query.get(2).map(String::as_str);
+
+    let mut context = create_query_context(&args.profile, None, None).await?;
+    context.args.concise = args.concise;
+    context.args.no_spinner = args.no_spinner;
+    context.args.verbose = args.verbose;
+    context.args.read_only = args.read_only;
+
+    let Some(name) = name else {
+        // Status of all engines
+        let all = "SELECT engine_name, status, type, nodes, clusters FROM information_schema.engines ORDER BY engine_name";
+        return query(&mut context, all.to_string()).await;
+    };
+
+    if action == "status" {
+        let status = format!(
+            "SELECT engine_name, status, type, nodes, clusters FROM information_schema.engines WHERE engine_name = {}",
+            sql_literal(name)
+        );
+        return query(&mut context, status).await;
+    }
+
+    let changes = EngineConfig::from_args(args)?;
+    let statement = engine_statement(action, name, &changes)?;
+    let old = if action == "resize" { Some(EngineConfig::from_row(&engine_row(&mut context, name).await?)) } else { None };
+
+    query(&mut context, statement).await?;
+
+    if let Some(old) = old {
+        println!("Old: {}", old);
+        println!("New: {}", old.apply(&changes));
+    }
+
+    let target = match action {
+        "start" => "RUNNING",
+        "stop" => "STOPPED",
+        _ => return Ok(()),
+    };
+    if args.wait {
+        let timeout = if args.wait_timeout > 0 { args.wait_timeout } else { DEFAULT_WAIT_TIMEOUT };
+        wait_for_status(&mut context, name, target, Duration::from_secs(timeout)).await?;
+        println!("Engine '{}' is {}.", name, target);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(engine_type: &str, nodes: usize, clusters: usize) -> EngineConfig {
+        EngineConfig { engine_type: engine_type.to_string(), nodes, clusters }
+    }
+
+    #[test]
+    fn test_engine_statement() {
+        let none = EngineConfig::default();
+        assert_eq!(engine_statement("start", "my_engine", &none).unwrap(), "START ENGINE \"my_engine\"");
+        assert_eq!(engine_statement("stop", "a\"b", &none).unwrap(), "STOP ENGINE \"a\"\"b\"");
+        assert_eq!(engine_statement("drop", "e", &none).unwrap(), "DROP ENGINE \"e\"");
+        assert_eq!(engine_statement("create", "e", &none).unwrap(), "CREATE ENGINE \"e\"");
+        let create = engine_statement("create", "e", &config("M", 2, 1)).unwrap();
+        assert_eq!(create, "CREATE ENGINE \"e\" WITH TYPE = M NODES = 2 CLUSTERS = 1");
+        assert_eq!(engine_statement("resize", "e", &config("", 4, 0)).unwrap(), "ALTER ENGINE \"e\" SET NODES = 4");
+        assert!(engine_statement("resize", "e", &none).is_err());
+        assert!(engine_statement("restart", "e", &none).is_err());
+    }
+
+    #[test]
+    fn test_stopped_engine() {
+        let mut context = Context::new(crate::args::get_args().unwrap());
+        context.args.extra = vec![String::from("engine=session_engine")];
+        let stopped = |body: &str| stopped_engine(&context, body);
+
+        assert_eq!(stopped("Engine 'reporting' is not running. Start the engine first."), Some(String::from("reporting")));
+        assert_eq!(stopped(r#"{"errors":[{"description":"engine \"my-engine\" is stopped"}]}"#), Some(String::from("my-engine")));
+        assert_eq!(stopped("Error: engine etl was stopped"), Some(String::from("etl")));
+        assert_eq!(stopped("The engine is not running"), Some(String::from("session_engine")));
+        assert_eq!(stopped("Engine 'reporting' does not exist"), None);
+        assert_eq!(stopped("relation \"engine_runs\" does not exist"), None);
+    }
+
+    #[test]
+    fn test_engine_config() {
+        let row: Row = vec![
+            (String::from("status"), Some(String::from("RUNNING"))),
+            (String::from("type"), Some(String::from("S"))),
+            (String::from("nodes"), Some(String::from("1"))),
+            (String::from("clusters"), Some(String::from("1"))),
+        ];
+        let old = EngineConfig::from_row(&row);
+        assert_eq!(old, config("S", 1, 1));
+        assert_eq!(old.to_string(), "type S, 1 node, 1 cluster");
+
+        let new = old.apply(&config("L", 3, 0));
+        assert_eq!(new.to_string(), "type L, 3 nodes, 1 cluster");
+
+        let mut args = crate::args::get_args().unwrap();
+        args.engine_type = String::from("xl");
+        assert_eq!(EngineConfig::from_args(&args).unwrap().engine_type, "XL");
+        args.engine_type = String::from("S CLUSTERS = 100");
+        assert!(EngineConfig::from_args(&args).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use pest::Parser;
+
+use crate::context::Context;
+use crate::query::{Rule, SQLParser};
+use crate::session::current_engine;
+use crate::utils::{confirm, is_interactive};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    // Unquoted word, upper-cased
+    Word(String),
+    Symbol(char),
+    // String literal or quoted identifier
+    Quoted,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatementKind {
+    // Only reads data
+    Read,
+    // May modify data or the schema
+    Write,
+    // Drops or wipes data; the reason names the statement
+    Destructive(String),
+}
+
+// Statements that only read: SELECT, WITH ... SELECT, SHOW, DESCRIBE, EXPLAIN of a read, VALUES, USE.
+const READ_KEYWORDS: &[&str] = &["SELECT", "WITH", "SHOW", "DESCRIBE", "DESC", "VALUES", "USE"];
+// Keywords that make a WITH statement write, e.g. WITH ... INSERT INTO
+const WRITE_KEYWORDS: &[&str] = &["INSERT", "UPDATE", "DELETE", "DROP", "TRUNCATE", "ALTER", "CREATE", "COPY", "VACUUM"];
+// Procedures that only read, e.g. the status lookup of asynchronous queries
+const READ_PROCEDURES: &[&str] = &["FB_GETASYNCSTATUS"];
+
+// Split a statement into tokens, dropping whitespace and comments. Unterminated literals become symbols.
+fn tokenize(sql: &str) -> Vec<Token> {
+    let Ok(mut pairs) = SQLParser::parse(Rule::tokens, sql) else {
+        return vec![];
+    };
+    let Some(tokens) = pairs.next() else {
+        return vec![];
+    };
+
+    tokens
+        .into_inner()
+        .filter_map(|pair| match pair.as_rule() {
+            Rule::word => Some(Token::Word(pair.as_str().to_uppercase())),
+            Rule::symbol => pair.as_str().chars().next().map(Token::Symbol),
+            Rule::string | Rule::e_string | Rule::quoted_identifier | Rule::raw_string => Some(Token::Quoted),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_word(tokens: &[Token], word: &str) -> bool {
+    tokens.iter().any(|t| matches!(t, Token::Word(w) if w == word))
+}
+
+// Classify a text of one or more statements by its most dangerous statement, so a write cannot hide behind a read.
+pub fn classify(sql: &str) -> StatementKind {
+    let tokens = tokenize(sql);
+    let kinds: Vec<StatementKind> = tokens.split(|t| *t == Token::Symbol(';')).map(classify_statement).collect();
+    if let Some(destructive) = kinds.iter().find(|kind| matches!(kind, StatementKind::Destructive(_))) {
+        return destructive.clone();
+    }
+    if kinds.contains(&StatementKind::Write) {
+        StatementKind::Write
+    } else {
+        StatementKind::Read
+    }
+}
+
+// Classify the tokens of a single statement.
+fn classify_statement(tokens: &[Token]) -> StatementKind {
+    // Leading parentheses as in (SELECT ...) UNION ...
+    let mut rest = tokens;
+    while let [Token::Symbol('('), tail @ ..] = rest {
+        rest = tail;
+    }
+
+    // EXPLAIN [ANALYZE] [(options)] statement is classified by the statement
+    if let [Token::Word(w), tail @ ..] = rest {
+        if w == "EXPLAIN" {
+            let mut tail = tail;
+            loop {
+                match tail {
+                    [Token::Word(w), more @ ..] if w == "ANALYZE" || w == "VERBOSE" => tail = more,
+                    [Token::Symbol('('), ..] => {
+                        let close = tail.iter().position(|t| *t == Token::Symbol(')')).unwrap_or(tail.len() - 1);
+                        tail = &tail[close + 1..];
+                    }
+                    _ => break,
+                }
+            }
+            rest = tail;
+        }
+    }
+
+    let Some(Token::Word(first)) = rest.first() else {
+        // Empty or only comments
+        return if rest.is_empty() { StatementKind::Read } else { StatementKind::Write };
+    };
+
+    match first.as_str() {
+        "WITH" if WRITE_KEYWORDS.iter().any(|w| has_word(rest, w)) => StatementKind::Write,
+        first if READ_KEYWORDS.contains(&first) => StatementKind::Read,
+        "CALL" if matches!(rest.get(1), Some(Token::Word(w)) if READ_PROCEDURES.contains(&w.as_str())) => StatementKind::Read,
+        "DROP" => StatementKind::Destructive(String::from("DROP")),
+        "TRUNCATE" => StatementKind::Destructive(String::from("TRUNCATE")),
+        "DELETE" if !has_word(rest, "WHERE") => StatementKind::Destructive(String::from("DELETE without WHERE")),
+        "UPDATE" if !has_word(rest, "WHERE") => StatementKind::Destructive(String::from("UPDATE without WHERE")),
+        "ALTER" if has_word(rest, "DROP") => StatementKind::Destructive(String::from("ALTER ... DROP")),
+        _ => StatementKind::Write,
+    }
+}
+
+// The statement on one line, shortened to fit a prompt.
+fn statement_summary(sql: &str) -> String {
+    const MAX_CHARS: usize = 80;
+    let line = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= MAX_CHARS {
+        line
+    } else {
+        line.chars().take(MAX_CHARS - 3).collect::<String>() + "..."
+    }
+}
+
+// Confirmation prompt naming the profile, engine and database a statement runs against, and the statement itself.
+fn confirmation_prompt(context: &Context, reason: &str, sql: &str) -> String {
+    let profile = if context.args.profile.is_empty() { "default" } else { context.args.profile.as_str() };
+    let engine = current_engine(context).unwrap_or_else(|| String::from("system"));
+    let database = if context.args.database.is_empty() { "(none)" } else { context.args.database.as_str() };
+    format!(
+        "{} on profile '{}', engine '{}', database '{}':\n  {}\nContinue? [y/N] ",
+        reason,
+        profile,
+        engine,
+        database,
+        statement_summary(sql)
+    )
+}
+
+// Refuse writes in read-only mode and ask for confirmation of destructive statements in an interactive terminal.
+pub fn check_statement(context: &Context, sql: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let kind = classify(sql);
+    if context.args.read_only && kind != StatementKind::Read {
+        return Err("Read-only mode: only SELECT, SHOW, DESCRIBE and EXPLAIN statements are allowed".into());
+    }
+
+    let StatementKind::Destructive(reason) = kind else {
+        return Ok(());
+    };
+    if !is_interactive() || confirm(&confirmation_prompt(context, &reason, sql))? {
+        Ok(())
+    } else {
+        Err("Statement cancelled".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::get_args;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("select 'drop' /* drop */, \"Drop\" -- drop\nfrom t;"),
+            vec![
+                Token::Word(String::from("SELECT")),
+                Token::Quoted,
+                Token::Symbol(','),
+                Token::Quoted,
+                Token::Word(String::from("FROM")),
+                Token::Word(String::from("T")),
+                Token::Symbol(';'),
+            ]
+        );
+        assert_eq!(tokenize("E'it\\'s'"), vec![Token::Quoted]);
+        assert_eq!(tokenize("'open"), vec![Token::Symbol('\''), Token::Word(String::from("OPEN"))]);
+    }
+
+    #[test]
+    fn test_classify_reads() {
+        for sql in [
+            "SELECT 1;",
+            "  -- comment\n select * from t",
+            "(select 1) union all (select 2)",
+            "with x as (select 1) select * from x",
+            "show tables",
+            "describe t",
+            "explain analyze select 1",
+            "explain (logical) select 1",
+            "CALL fb_GetAsyncStatus('abc');",
+            "",
+            "/* only a comment */",
+        ] {
+            assert_eq!(classify(sql), StatementKind::Read, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_classify_writes() {
+        for sql in [
+            "insert into t values (1)",
+            "create table t (a int)",
+            "delete from t where a = 1",
+            "update t set a = 1 where a = 2",
+            "alter table t add column b int",
+            "explain analyze insert into t select 1",
+            "with x as (select 1) insert into t select * from x",
+            "vacuum t",
+            "call my_procedure()",
+        ] {
+            assert_eq!(classify(sql), StatementKind::Write, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_classify_destructive() {
+        let destructive = |reason: &str| StatementKind::Destructive(reason.to_string());
+        assert_eq!(classify("DROP TABLE orders;"), destructive("DROP"));
+        assert_eq!(classify("/* cleanup */ drop database prod"), destructive("DROP"));
+        assert_eq!(classify("truncate table t"), destructive("TRUNCATE"));
+        assert_eq!(classify("delete from t"), destructive("DELETE without WHERE"));
+        assert_eq!(classify("delete from t -- where a = 1"), destructive("DELETE without WHERE"));
+        assert_eq!(classify("update t set note = 'where'"), destructive("UPDATE without WHERE"));
+        assert_eq!(classify("alter table t drop column a"), destructive("ALTER ... DROP"));
+        assert_eq!(classify("explain drop table t"), destructive("DROP"));
+    }
+
+    #[test]
+    fn test_classify_multiple_statements() {
+        let destructive = |reason: &str| StatementKind::Destructive(reason.to_string());
+        assert_eq!(classify("select 1; select 2;"), StatementKind::Read);
+        assert_eq!(classify("select 1; insert into t values (1)"), StatementKind::Write);
+        assert_eq!(classify("select 1; drop table t"), destructive("DROP"));
+        assert_eq!(classify("delete from t; select 1 where true"), destructive("DELETE without WHERE"));
+        assert_eq!(classify("insert into t values (1); truncate table t;"), destructive("TRUNCATE"));
+        // Semicolons in literals and comments do not split
+        assert_eq!(classify("select ';drop table t'; -- ; drop table t"), StatementKind::Read);
+    }
+
+    #[test]
+    fn test_read_only() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.read_only = true;
+        assert!(check_statement(&context, "select 1").is_ok());
+        let error = check_statement(&context, "insert into t values (1)").unwrap_err().to_string();
+        assert!(error.starts_with("Read-only mode"), "{}", error);
+        assert!(check_statement(&context, "drop table t").is_err());
+        assert!(check_statement(&context, "select 1; drop table t").is_err());
+        assert!(check_statement(&context, "select 1; insert into t values (1);").is_err());
+    }
+
+    #[test]
+    fn test_confirmation_prompt() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.database = String::from("prod_db");
+        context.args.extra = vec![String::from("engine=prod_engine")];
+        context.args.host = String::from("localhost:8123");
+        let prompt = confirmation_prompt(&context, "DROP", "drop table\n  orders;");
+        assert!(prompt.starts_with("DROP on profile '"), "{}", prompt);
+        assert!(prompt.ends_with("engine 'prod_engine', database 'prod_db':\n  drop table orders;\nContinue? [y/N] "), "{}", prompt);
+    }
+
+    #[test]
+    fn test_statement_summary() {
+        assert_eq!(statement_summary("DROP DATABASE \"sales\""), "DROP DATABASE \"sales\"");
+        let summary = statement_summary(&format!("delete from t -- {}", "x".repeat(100)));
+        assert_eq!(summary.chars().count(), 80);
+        assert!(summary.ends_with("xx..."), "{}", summary);
+    }
+}
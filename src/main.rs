@@ -1,19 +1,37 @@
 use rustyline::{config::Configurer, error::ReadlineError, Cmd, DefaultEditor, EventHandler, KeyCode, KeyEvent, Modifiers};
 use std::io::IsTerminal;
+use std::time::Instant;
 
 mod args;
+mod async_query;
 mod auth;
+mod conditional;
 mod context;
+mod database;
+mod describe;
+mod engine;
+mod guard;
 mod meta_commands;
+mod plot;
 mod query;
+mod render;
+mod result;
+mod script;
+mod session;
 mod show;
+mod stats;
+mod summarize;
+mod template;
+mod timing;
 mod utils;
 
-use args::get_args;
+use args::get_args_with_config;
+use async_query::{report_finished, run_statement, REPORT_INTERVAL};
 use auth::maybe_authenticate;
 use context::Context;
-use meta_commands::handle_meta_command;
+use meta_commands::unknown_command_message;
 use query::{query, try_split_queries};
+use script::{process_line, LineResult};
 use utils::history_path;
 
 pub const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -27,10 +45,16 @@ fn print_help() {
     println!("    fb auth [SUBCOMMAND]");
     println!("    fb use <database|engine> <name>");
     println!("    fb show <databases|engines>");
+    println!("    fb database <create|drop|describe> <NAME>");
+    println!("    fb engine <start|stop|create|drop|resize|status> [NAME]");
+    println!("    fb submit <QUERY> | status <TOKEN> | wait <TOKEN>");
     println!();
     println!("QUERY EXECUTION:");
     println!("    fb \"SELECT 42\"              Run a single query");
     println!("    fb                           Start interactive REPL");
+    println!("    fb submit \"INSERT ...\"       Start a query asynchronously and print its token");
+    println!("    fb status <TOKEN>            Show the status of an asynchronous query");
+    println!("    fb wait <TOKEN>              Wait until an asynchronous query finishes");
     println!();
     println!("AUTHENTICATION:");
     println!("    fb auth                      Interactive authentication setup");
@@ -41,16 +65,30 @@ fn print_help() {
     println!("CONFIGURATION:");
     println!("    fb use database <name>       Set default database");
     println!("    fb use engine <name>         Set default engine (resolves endpoint)");
+    println!("    fb use read-only <on|off>    Refuse statements that modify data for this profile");
     println!();
     println!("DISCOVERY:");
     println!("    fb show databases            List all available databases");
     println!("    fb show engines              List all available engines");
     println!();
+    println!("DATABASES:");
+    println!("    fb database create <NAME>    Create a database (--description to describe it)");
+    println!("    fb database describe <NAME>  Show owner, size, tables and views of a database");
+    println!("    fb database drop <NAME>      Drop a database, after confirmation in a terminal");
+    println!();
+    println!("ENGINES:");
+    println!("    fb engine status [NAME]      Show status and size of engines");
+    println!("    fb engine start <NAME>       Start an engine (--wait to wait until it is running)");
+    println!("    fb engine stop <NAME>        Stop an engine (--wait to wait until it is stopped)");
+    println!("    fb engine create <NAME>      Create an engine (--type, --nodes, --clusters)");
+    println!("    fb engine resize <NAME>      Change type, nodes or clusters of an engine");
+    println!("    fb engine drop <NAME>        Drop an engine");
+    println!();
     println!("OPTIONS:");
     println!("    --database <NAME>            Database name (transient override)");
     println!("    -d <NAME>                    Alias for --database");
     println!("    --host <HOSTNAME>            Hostname (transient override)");
-    println!("    --format <FORMAT>            Output format (PSQL, TabSeparatedWithNames, etc.)");
+    println!("    --format <FORMAT>            Output format (PSQL, TabSeparatedWithNames, markdown, html, csv, etc.)");
     println!("    --label <LABEL>              Query label for tracking");
     println!("    --sa-id <ID>                 Service Account ID (transient)");
     println!("    --sa-secret <SECRET>         Service Account Secret (transient)");
@@ -59,6 +97,27 @@ fn print_help() {
     println!("    --concise                    Suppress time statistics");
     println!("    --no-spinner                 Disable spinner");
     println!("    --no-keyring                 Store secrets in file instead of OS keychain");
+    println!("    --profile <NAME>             Use a named credentials profile (also for 'fb auth')");
+    println!("    --read-only                  Refuse statements other than SELECT, SHOW, DESCRIBE and EXPLAIN");
+    println!("    --pset <NAME>=<VALUE>        Client-side output option, e.g. --pset null=NULL (see \\pset)");
+    println!("    --stats-json <FILE|->        Write per-statement statistics as JSON lines (- for stdout)");
+    println!("    --timing <on|off|detailed>   Report query time; detailed adds connection, transfer and server stats");
+    println!("    --expanded <on|off|auto>     Vertical display of PSQL results, auto when too wide");
+    println!("    --max-result-memory <SIZE>   Largest result kept for \\last and \\export, 0 disables (default: 64MB)");
+    println!("    --max-rows <N>               Stop reading a result after N rows and cancel the query");
+    println!("    --max-bytes <SIZE>           Stop reading a result after SIZE bytes and cancel the query");
+    println!("    --confirm-bytes <SIZE>       Ask before reading larger responses in a terminal (default: 100MB)");
+    println!("    --template <TEMPLATE>        Print each row through a template, e.g. '{{name}}: {{status|default:-}}'");
+    println!("    --template-file <PATH>       Read the row template from a file");
+    println!("    --plot <SPEC>                Plot results as a chart, e.g. 'bar day total' (see \\plot)");
+    println!("    --summarize                  Print per-column statistics instead of result rows");
+    println!("    --description <TEXT>         Description for fb database create");
+    println!("    --auto-start-engine          Start a stopped engine without asking and run the statement again");
+    println!("    --wait                       Wait for fb engine start/stop to finish");
+    println!("    --wait-timeout <SECONDS>     Give up waiting after SECONDS (default: 600)");
+    println!("    --type <TYPE>                Node type for fb engine create/resize (S, M, L, XL)");
+    println!("    --nodes <N>                  Number of nodes for fb engine create/resize");
+    println!("    --clusters <N>               Number of clusters for fb engine create/resize");
     println!("    --version                    Print version");
     println!("    --help                       Show this help message");
     println!();
@@ -83,7 +142,7 @@ fn print_help() {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = get_args()?;
+    let (args, config_extra) = get_args_with_config()?;
 
     if args.version {
         println!("fb-cli version {}", CLI_VERSION);
@@ -101,9 +160,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Check for auth subcommands (use positional words instead of flags)
         if args.query.len() > 1 {
             match args.query[1].as_str() {
-                "check" | "status" => return auth::show_auth_status(args.no_keyring),
-                "clear" | "logout" => return auth::clear_auth(args.no_keyring),
-                "token" => return auth::print_access_token(args.no_keyring).await,
+                "check" | "status" => return auth::show_auth_status(&args.profile, args.no_keyring),
+                "clear" | "logout" => return auth::clear_auth(&args.profile, args.no_keyring),
+                "token" => return auth::print_access_token(&args.profile, args.no_keyring).await,
                 _ => {
                     eprintln!("Unknown auth subcommand: {}", args.query[1]);
                     eprintln!("Available: fb auth check, fb auth clear, fb auth token");
@@ -112,7 +171,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        return auth::interactive_auth_setup(args.no_keyring).await;
+        return auth::interactive_auth_setup(&args.profile, args.no_keyring).await;
     }
 
     // Handle 'use' subcommand for setting database/engine
@@ -120,21 +179,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         if args.query.len() < 3 {
             eprintln!("Usage: fb use database <name>");
             eprintln!("       fb use engine <name>");
+            eprintln!("       fb use read-only <on|off>");
             std::process::exit(1);
         }
 
         match args.query[1].as_str() {
             "database" => {
                 let database_name = args.query[2].clone();
-                return auth::set_default_database(database_name, args.no_keyring).await;
+                return auth::set_default_database(&args.profile, database_name, args.no_keyring).await;
             }
             "engine" => {
                 let engine_name = args.query[2].clone();
-                return auth::set_default_engine(engine_name, args.no_keyring).await;
+                return auth::set_default_engine(&args.profile, engine_name, args.no_keyring).await;
             }
+            "read-only" | "read_only" => return auth::set_read_only(&args.profile, &args.query[2]),
             _ => {
                 eprintln!("Unknown use target: {}", args.query[1]);
-                eprintln!("Available: database, engine");
+                eprintln!("Available: database, engine, read-only");
                 std::process::exit(1);
             }
         }
@@ -150,10 +211,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         match args.query[1].as_str() {
             "databases" | "database" => {
-                return show::show_databases().await;
+                return show::show_databases(&args.profile).await;
             }
             "engines" | "engine" => {
-                return show::show_engines().await;
+                return show::show_engines(&args.profile).await;
             }
             _ => {
                 eprintln!("Unknown show target: {}", args.query[1]);
@@ -163,7 +224,68 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Handle 'database' subcommand for database management
+    if !args.query.is_empty() && args.query[0] == "database" {
+        let action = args.query.get(1).map(String::as_str).unwrap_or_default();
+        match action {
+            "create" | "drop" | "describe" if args.query.len() == 3 => return database::database_command(&args).await,
+            "create" | "drop" | "describe" | "" => {
+                eprintln!("Usage: fb database <create|drop|describe> <name>");
+                std::process::exit(1);
+            }
+            _ => {
+                eprintln!("Unknown database command: {}", action);
+                eprintln!("Available: create, drop, describe");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Handle 'engine' subcommand for engine lifecycle management
+    if !args.query.is_empty() && args.query[0] == "engine" {
+        let action = args.query.get(1).map(String::as_str).unwrap_or_default();
+        match action {
+            "status" => return engine::engine_command(&args).await,
+            "start" | "stop" | "create" | "drop" | "resize" if args.query.len() == 3 => return engine::engine_command(&args).await,
+            "start" | "stop" | "create" | "drop" | "resize" | "" => {
+                eprintln!("Usage: fb engine <start|stop|create|drop|resize> <name>");
+                eprintln!("       fb engine status [name]");
+                std::process::exit(1);
+            }
+            _ => {
+                eprintln!("Unknown engine command: {}", action);
+                eprintln!("Available: start, stop, create, drop, resize, status");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Handle 'submit', 'status' and 'wait' subcommands for asynchronous queries
+    if !args.query.is_empty() && ["submit", "status", "wait"].contains(&args.query[0].as_str()) {
+        if args.query.len() < 2 {
+            eprintln!("Usage: fb submit \"<query>\"");
+            eprintln!("       fb status <token>");
+            eprintln!("       fb wait <token>");
+            std::process::exit(1);
+        }
+
+        let subcommand = args.query[0].clone();
+        let argument = args.query[1..].join(" ");
+        let mut context = Context::new(args);
+        context.set_config_extra(config_extra);
+        maybe_authenticate(&mut context).await?;
+        return match subcommand.as_str() {
+            "submit" => {
+                println!("{}", async_query::submit(&mut context, argument).await?);
+                Ok(())
+            }
+            "status" => async_query::print_status(&async_query::fetch_status(&mut context, &argument).await?),
+            _ => async_query::print_status(&async_query::wait(&mut context, &argument).await?),
+        };
+    }
+
     let mut context = Context::new(args);
+    context.set_config_extra(config_extra);
     maybe_authenticate(&mut context).await?;
 
     let query_text = if context.args.command.is_empty() {
@@ -173,8 +295,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     if !query_text.is_empty() {
-        query(&mut context, query_text).await?;
-        return Ok(());
+        // Statements are run one by one as in the REPL, so each is checked and recorded on its own
+        let queries = try_split_queries(&format!("{};", query_text)).unwrap_or_else(|| vec![query_text]);
+        let mut result = Ok(());
+        for q in queries {
+            if let Err(e) = query(&mut context, q).await {
+                result = Err(e);
+            }
+        }
+        return result;
     }
 
     let is_tty = std::io::stdout().is_terminal() && std::io::stdin().is_terminal();
@@ -196,8 +325,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         eprintln!("Press Ctrl+D to exit.");
     }
     let mut buffer: String = String::new();
+    let mut initial_input: String = String::new();
     let mut has_error = false;
+    let mut last_report = Instant::now();
     loop {
+        // Finished \async queries are reported in interactive sessions only, at most every REPORT_INTERVAL
+        if is_tty && !context.async_queries.is_empty() && last_report.elapsed() >= REPORT_INTERVAL {
+            report_finished(&mut context).await;
+            last_report = Instant::now();
+        }
+
         let prompt = if !is_tty {
             // No prompt when stdout is not a terminal (e.g., piped)
             ""
@@ -223,41 +360,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "=> "
             }
         };
-        let readline = rl.readline(prompt);
+        let readline = rl.readline_with_initial(prompt, (initial_input.as_str(), ""));
+        initial_input.clear();
 
         match readline {
             Ok(line) => {
-                buffer += line.as_str();
-
-                if buffer.trim() == "quit" || buffer.trim() == "exit" {
+                if buffer.trim().is_empty() && (line.trim() == "quit" || line.trim() == "exit") {
                     break;
                 }
 
-                buffer += "\n";
-                if !line.is_empty() {
-                    // Check if this is a meta-command (backslash command)
-                    if line.trim().starts_with('\\') {
-                        if let Err(e) = handle_meta_command(&mut context, line.trim()) {
-                            eprintln!("Error processing meta-command: {}", e);
-                        }
-                        buffer.clear();
-                        continue;
+                let queries = match process_line(&mut context, &mut buffer, &line).await {
+                    Ok(LineResult::Statements(queries)) => queries,
+                    Ok(LineResult::Consumed) => vec![],
+                    Ok(LineResult::UnknownCommand) => {
+                        eprintln!("{}", unknown_command_message(line.trim()));
+                        has_error = true;
+                        vec![]
+                    }
+                    Err(e) => {
+                        eprintln!("Error processing meta-command: {}", e);
+                        has_error = true;
+                        vec![]
                     }
+                };
 
-                    let queries = try_split_queries(&buffer).unwrap_or_default();
+                // Text returned from the editor is run if complete, otherwise re-inserted for editing
+                let queries = match context.pending_input.take() {
+                    Some(text) if text.ends_with(';') => {
+                        buffer = text + "\n";
+                        try_split_queries(&buffer).unwrap_or_default()
+                    }
+                    Some(text) => {
+                        initial_input = text;
+                        continue;
+                    }
+                    None => queries,
+                };
 
-                    if !queries.is_empty() {
-                        rl.add_history_entry(buffer.trim())?;
-                        rl.append_history(&history_path)?;
+                if !queries.is_empty() {
+                    rl.add_history_entry(buffer.trim())?;
+                    rl.append_history(&history_path)?;
+                    // Recorded here rather than in query::query, so internal queries do not replace it
+                    context.last_query = Some(buffer.trim().to_string());
 
-                        for q in queries {
-                            if query(&mut context, q).await.is_err() {
-                                has_error = true;
-                            }
+                    for q in queries {
+                        if run_statement(&mut context, q).await.is_err() {
+                            has_error = true;
                         }
-
-                        buffer.clear();
                     }
+
+                    buffer.clear();
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -265,6 +417,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 buffer.clear();
             }
             Err(ReadlineError::Eof) => {
+                if context.conditionals.depth() > 0 {
+                    eprintln!("Reached end of input with {} unterminated \\if block(s)", context.conditionals.depth());
+                    has_error = true;
+                }
                 if !buffer.trim().is_empty() {
                     buffer += ";";
                     match try_split_queries(&buffer) {
@@ -273,7 +429,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             for q in queries {
                                 rl.add_history_entry(q.trim())?;
                                 rl.append_history(&history_path)?;
-                                if query(&mut context, q).await.is_err() {
+                                if run_statement(&mut context, q).await.is_err() {
                                     has_error = true;
                                 }
                             }
@@ -294,6 +450,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         eprintln!("Saved history to {:?}", history_path)
     }
 
+    for token in &context.async_queries {
+        eprintln!("Async query {} is still running; see fb status {}", token, token);
+    }
+
     if has_error {
         Err("One or more queries failed".into())
     } else {
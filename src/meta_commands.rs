@@ -1,9 +1,372 @@
+use crate::async_query::run_statement;
 use crate::context::Context;
+use crate::describe::describe;
+use crate::plot::{plot, terminal_width, PlotSpec};
+use crate::query::{capture_query, capture_tsv, interpolate_variables, try_split_queries};
+use crate::render::{render, render_display, Expanded, Format, FORMAT_NAMES, PRINT_OPTION_NAMES};
+use crate::script::run_file;
+use crate::summarize::summarize;
+use crate::timing::Timing;
+use crate::session::{connect, parse_connect_args, print_conninfo, print_settings};
+use crate::utils::{edit_file, edit_text, editor_command, run_shell};
 use regex::Regex;
 use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Help entry of a meta-command
+pub struct MetaCommand {
+    pub names: &'static [&'static str],
+    pub syntax: &'static str,
+    pub description: &'static str,
+    pub details: &'static str,
+}
+
+// Registry of meta-commands, shown by \?
+pub static META_COMMANDS: &[MetaCommand] = &[
+    MetaCommand {
+        names: &["?"],
+        syntax: "\\? [COMMAND]",
+        description: "Show help on meta-commands",
+        details: "Without arguments lists all meta-commands. With a command name, e.g. \\? dt, shows its details.",
+    },
+    MetaCommand {
+        names: &["set"],
+        syntax: "\\set PROMPT1|PROMPT2|PROMPT3 VALUE",
+        description: "Set a prompt",
+        details: "PROMPT1 is the normal prompt, PROMPT2 is shown while a statement continues on the next line \
+                  and PROMPT3 inside a transaction. The value may be quoted with single or double quotes.",
+    },
+    MetaCommand {
+        names: &["unset"],
+        syntax: "\\unset PROMPT1|PROMPT2|PROMPT3",
+        description: "Restore the default prompt",
+        details: "Resets a prompt set with \\set to its default.",
+    },
+    MetaCommand {
+        names: &["e"],
+        syntax: "\\e",
+        description: "Edit the current or previous statement in $EDITOR",
+        details: "Opens the statement being typed, or the previous statement when nothing is typed, in $VISUAL or \
+                  $EDITOR. When the editor exits, the text runs if it ends with ';' and is put back at the prompt otherwise.",
+    },
+    MetaCommand {
+        names: &["ef"],
+        syntax: "\\ef FILE",
+        description: "Edit a file in $EDITOR and run it",
+        details: "Opens FILE in $VISUAL or $EDITOR and runs its statements once the editor exits.",
+    },
+    MetaCommand {
+        names: &["i", "include"],
+        syntax: "\\i FILE",
+        description: "Run statements and meta-commands from a file",
+        details: "Statements run in the current session, so settings and transactions carry over. Includes may be \
+                  nested; cycles are rejected and errors report the file and line of every include level.",
+    },
+    MetaCommand {
+        names: &["ir", "include_relative"],
+        syntax: "\\ir FILE",
+        description: "Like \\i, relative to the file being executed",
+        details: "Inside an included file, FILE is resolved relative to that file's directory. Elsewhere it \
+                  behaves like \\i.",
+    },
+    MetaCommand {
+        names: &["d"],
+        syntax: "\\d[+] [TABLE]",
+        description: "List relations, or describe a table",
+        details: "Without arguments lists tables and views. With a table name shows its columns, types, \
+                  nullability, primary index and partitioning; \\d+ also shows row counts and sizes.",
+    },
+    MetaCommand {
+        names: &["dt"],
+        syntax: "\\dt[+] [PATTERN]",
+        description: "List tables",
+        details: "PATTERN may be schema-qualified and use * and ? wildcards. \\dt+ adds row counts and sizes.",
+    },
+    MetaCommand {
+        names: &["dv"],
+        syntax: "\\dv[+] [PATTERN]",
+        description: "List views",
+        details: "PATTERN may be schema-qualified and use * and ? wildcards. \\dv+ adds view definitions.",
+    },
+    MetaCommand {
+        names: &["di"],
+        syntax: "\\di [PATTERN]",
+        description: "List indexes",
+        details: "Lists primary and aggregating indexes of tables matching PATTERN.",
+    },
+    MetaCommand {
+        names: &["l"],
+        syntax: "\\l [PATTERN]",
+        description: "List databases",
+        details: "Lists databases matching PATTERN, which may use * and ? wildcards.",
+    },
+    MetaCommand {
+        names: &["de"],
+        syntax: "\\de [PATTERN]",
+        description: "List engines",
+        details: "Lists engines matching PATTERN with their status and configuration.",
+    },
+    MetaCommand {
+        names: &["c", "connect"],
+        syntax: "\\c [--profile NAME] [DATABASE|-] [ENGINE|-]",
+        description: "Switch database, engine or profile",
+        details: "Use - to keep the current database or engine. Engines are resolved through the system engine. \
+                  Session parameters are reset and the new connection is shown.",
+    },
+    MetaCommand {
+        names: &["conninfo"],
+        syntax: "\\conninfo",
+        description: "Show the current connection",
+        details: "Shows the profile, account, auth method, token expiry, host, engine, database and protocol version.",
+    },
+    MetaCommand {
+        names: &["settings"],
+        syntax: "\\settings",
+        description: "Show session settings and where they came from",
+        details: "Lists every session setting with its origin: CLI --extra, config file, set statement or server \
+                  header. Values are masked with --hide-pii.",
+    },
+    MetaCommand {
+        names: &["g"],
+        syntax: "\\g [FILE]",
+        description: "Run the query buffer, optionally writing the result to FILE",
+        details: "Runs the statement typed so far, or the last query if the buffer is empty. With FILE the result \
+                  is written to the file instead of the terminal.",
+    },
+    MetaCommand {
+        names: &["gx"],
+        syntax: "\\gx [FILE]",
+        description: "Like \\g, but with Vertical output",
+        details: "Runs the query buffer once with Vertical output. The session format is not changed.",
+    },
+    MetaCommand {
+        names: &["gset"],
+        syntax: "\\gset [PREFIX]",
+        description: "Run the query buffer and store the result row in variables",
+        details: "The query must return exactly one row. Each column is stored in a variable named PREFIX followed \
+                  by the column name, NULL values unset the variable. Later statements refer to variables as \
+                  :name, :'name' (string literal) or :\"name\" (identifier).",
+    },
+    MetaCommand {
+        names: &["timing"],
+        syntax: "\\timing [on|off|detailed]",
+        description: "Toggle reporting of query time",
+        details: "detailed breaks the time down into DNS, connect and TLS (measured on a probe connection), time to \
+                  first byte, transfer time and body size, plus the server statistics of JSON and JSONLines_Compact \
+                  results. Without an argument toggles between on and off. The default can be set with --timing or \
+                  'timing' in the config file.",
+    },
+    MetaCommand {
+        names: &["async"],
+        syntax: "\\async",
+        description: "Run the next statement asynchronously in the background",
+        details: "Submits the next statement in Firebolt's async execution mode, e.g. a long INSERT ... SELECT or \
+                  COPY, prints its token and returns to the prompt. Interactive sessions report completion before a \
+                  prompt, checking at most every 5 seconds. Outside the REPL use fb submit, fb status and fb wait.",
+    },
+    MetaCommand {
+        names: &["x"],
+        syntax: "\\x [on|off|auto]",
+        description: "Toggle expanded (vertical) display of results",
+        details: "Shows PSQL results as one block per row, without changing the format requested from the server. \
+                  auto uses the vertical layout only for results wider than the terminal. Without an argument \
+                  toggles between on and off. The default can be set with --expanded or 'expanded' in the config \
+                  file.",
+    },
+    MetaCommand {
+        names: &["pset"],
+        syntax: "\\pset [NAME [VALUE]]",
+        description: "Show or set client-side output options",
+        details: "Options of locally rendered output (PSQL, Vertical and unaligned): null (text shown for NULL), \
+                  border (0, 1 or 2), linestyle (ascii or unicode), footer (row count), numericlocale (thousands \
+                  separators), tuples_only (rows without headers), fieldsep and recordsep (separators of unaligned \
+                  output, \\t and \\n allowed). Without VALUE booleans toggle and other options reset; without \
+                  NAME all options are listed. Defaults come from --pset or 'pset' in the config file, e.g. \
+                  pset: [\"null=∅\", \"linestyle=unicode\"]. \\pset expanded is the same as \\x.",
+    },
+    MetaCommand {
+        names: &["last"],
+        syntax: "\\last [FORMAT]",
+        description: "Show the last result again, optionally in another format",
+        details: "Results in PSQL, Vertical, JSONLines_Compact, unaligned, markdown, html, csv, asciidoc and latex \
+                  format are kept client-side when they fit in --max-result-memory. FORMAT is any of these, TSV, \
+                  CSVWithNames or JSON; the result is rendered locally without running the query again.",
+    },
+    MetaCommand {
+        names: &["plot"],
+        syntax: "\\plot [line|bar|spark] [X_COL] Y_COL...",
+        description: "Plot the last result as a terminal chart",
+        details: "Draws a line chart (default), bar chart or sparklines sized to the terminal width. With two or more \
+                  columns the first one is the x axis; time and numeric x axes are spaced by value. Without columns \
+                  the first column is the x axis unless it is numeric, and all numeric columns are plotted. \
+                  --plot SPEC does the same for every query.",
+    },
+    MetaCommand {
+        names: &["summarize"],
+        syntax: "\\summarize [COLUMN...]",
+        description: "Show per-column statistics of the last result",
+        details: "For every column (or the named ones) shows the type, null count, distinct count, min, max, mean of \
+                  numeric columns and the most frequent values, computed client-side. Distinct counts and top values \
+                  are exact up to 10000 distinct values and approximate (marked ~) beyond. --summarize prints this \
+                  instead of the rows of every query.",
+    },
+    MetaCommand {
+        names: &["export"],
+        syntax: "\\export FORMAT FILE",
+        description: "Write the last result to FILE",
+        details: "Renders the last result like \\last FORMAT and writes it to FILE, which is overwritten.",
+    },
+    MetaCommand {
+        names: &["o", "out"],
+        syntax: "\\o [FILE]",
+        description: "Send query results to FILE, or back to stdout",
+        details: "Results of following queries and \\qecho output are written to FILE, which is truncated first. \
+                  Without FILE output goes to stdout again.",
+    },
+    MetaCommand {
+        names: &["echo"],
+        syntax: "\\echo [-n] [TEXT]...",
+        description: "Print text to stdout",
+        details: "Arguments are joined with spaces. Single-quoted arguments keep their spaces and may contain \\n \
+                  and \\t. Variables are substituted as in statements, e.g. \\echo 'max id:' :max_id. -n omits \
+                  the trailing newline.",
+    },
+    MetaCommand {
+        names: &["qecho"],
+        syntax: "\\qecho [-n] [TEXT]...",
+        description: "Print text to the query output (see \\o)",
+        details: "Like \\echo, but written where query results go, so it can annotate an \\o file.",
+    },
+    MetaCommand {
+        names: &["warn"],
+        syntax: "\\warn [-n] [TEXT]...",
+        description: "Print text to stderr",
+        details: "Like \\echo, but written to stderr so it does not mix with results on stdout.",
+    },
+    MetaCommand {
+        names: &["if"],
+        syntax: "\\if EXPR",
+        description: "Begin a conditional block",
+        details: "EXPR is a boolean after variable substitution: true/false, yes/no, on/off or 1/0, e.g. \
+                  \\if :table_exists with a variable set by \\gset. Lines of skipped branches are not run. \
+                  Blocks nest and must be closed with \\endif.",
+    },
+    MetaCommand {
+        names: &["elif"],
+        syntax: "\\elif EXPR",
+        description: "Alternative branch of a conditional block",
+        details: "Runs when no previous branch of the block ran and EXPR is true.",
+    },
+    MetaCommand {
+        names: &["else"],
+        syntax: "\\else",
+        description: "Final branch of a conditional block",
+        details: "Runs when no previous branch of the block ran.",
+    },
+    MetaCommand {
+        names: &["endif"],
+        syntax: "\\endif",
+        description: "End a conditional block",
+        details: "Closes the innermost \\if block.",
+    },
+    MetaCommand {
+        names: &["!"],
+        syntax: "\\! [COMMAND]",
+        description: "Run a shell command, or start a shell",
+        details: "COMMAND runs through the system shell and its output is streamed to the terminal. Without a \
+                  command an interactive $SHELL is started.",
+    },
+    MetaCommand {
+        names: &["setenv"],
+        syntax: "\\setenv NAME [VALUE]",
+        description: "Set or unset an environment variable",
+        details: "The variable applies to commands run with \\! and to the editor, e.g. \\setenv EDITOR nano. \
+                  Without VALUE the variable is removed.",
+    },
+    MetaCommand {
+        names: &["cd"],
+        syntax: "\\cd [DIR]",
+        description: "Change the working directory",
+        details: "Relative paths of \\i, \\o, \\ef and shell commands are resolved from this directory. Without DIR \
+                  changes to the home directory.",
+    },
+];
+
+// Find a meta-command by name, ignoring a trailing "+"
+pub fn find_meta_command(name: &str) -> Option<&'static MetaCommand> {
+    let name = name.trim_start_matches('\\').trim_end_matches('+');
+    META_COMMANDS.iter().find(|c| c.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+}
+
+// Print the list of meta-commands, or details of one
+fn print_meta_help(topic: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(topic) = topic {
+        let entry = find_meta_command(topic).ok_or_else(|| unknown_command_message(&format!("\\{}", topic)))?;
+        println!("{}", entry.syntax);
+        println!("    {}", entry.description);
+        println!();
+        println!("    {}", entry.details);
+        return Ok(());
+    }
+
+    let width = META_COMMANDS.iter().map(|c| c.syntax.len()).max().unwrap_or(0);
+    for entry in META_COMMANDS {
+        println!("  {:width$}  {}", entry.syntax, entry.description);
+    }
+    Ok(())
+}
+
+// Levenshtein distance between two command names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Error message for a meta-command that could not be handled, with suggestions
+pub fn unknown_command_message(command: &str) -> String {
+    let name = command.trim().trim_start_matches('\\').split_whitespace().next().unwrap_or_default();
+
+    if let Some(entry) = find_meta_command(name) {
+        return format!("Invalid arguments for \\{}. Usage: {}", name, entry.syntax);
+    }
+
+    let lowercase = name.to_lowercase();
+    let mut suggestions: Vec<(usize, &str)> = META_COMMANDS
+        .iter()
+        .flat_map(|c| c.names.iter())
+        .map(|n| (edit_distance(&lowercase, n), *n))
+        .filter(|(distance, n)| *distance <= 2.min(n.len()) || (lowercase.len() > 1 && n.starts_with(lowercase.as_str())))
+        .collect();
+    suggestions.sort();
+
+    if suggestions.is_empty() {
+        format!("Invalid command \\{}. Try \\? for help.", name)
+    } else {
+        let names: Vec<String> = suggestions.iter().take(3).map(|(_, n)| format!("\\{}", n)).collect();
+        format!("Invalid command \\{}. Did you mean {}? Try \\? for help.", name, names.join(", "))
+    }
+}
 
 // Handle meta-commands (backslash commands)
-pub fn handle_meta_command(context: &mut Context, command: &str) -> Result<bool, Box<dyn std::error::Error>> {
+pub async fn handle_meta_command(context: &mut Context, command: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    // Handle \? command
+    if let Some(topic) = parse_help(command) {
+        print_meta_help(topic.as_deref())?;
+        return Ok(true);
+    }
+
     // Handle \set PROMPT1 command
     if let Some(prompt) = parse_set_prompt(command, "PROMPT1") {
         context.set_prompt1(prompt);
@@ -40,9 +403,287 @@ pub fn handle_meta_command(context: &mut Context, command: &str) -> Result<bool,
         return Ok(true);
     }
 
+    // Handle \e command
+    if parse_edit(command) {
+        edit_query(context, &editor_command())?;
+        return Ok(true);
+    }
+
+    // Handle \ef command
+    if let Some(path) = parse_edit_file(command) {
+        edit_file(Path::new(&path), &editor_command())?;
+        let mut text = fs::read_to_string(&path)?.trim_end().to_string();
+        // The file is run once the editor exits, even if its last statement lacks a semicolon
+        if !text.is_empty() && !text.ends_with(';') {
+            text.push(';');
+        }
+        context.query_buffer.clear();
+        context.pending_input = Some(text);
+        return Ok(true);
+    }
+
+    // Handle \i and \ir commands
+    if let Some((path, relative)) = parse_include(command) {
+        let path = PathBuf::from(path);
+        // \ir resolves relative to the file being executed, \i relative to the working directory
+        let path = match context.include_stack.last().and_then(|current| current.parent()) {
+            Some(dir) if relative && path.is_relative() => dir.join(path),
+            _ => path,
+        };
+        run_file(context, &path).await?;
+        return Ok(true);
+    }
+
+    // Handle \timing command
+    if let Some(mode) = parse_timing(command) {
+        let timing = match mode {
+            Some(mode) => {
+                Timing::parse(&mode).ok_or_else(|| format!("Invalid value '{}' for \\timing: expected on, off or detailed", mode))?
+            }
+            None if Timing::parse(&context.args.timing).unwrap_or(Timing::On) == Timing::Off => Timing::On,
+            None => Timing::Off,
+        };
+        context.args.timing = timing.to_string();
+        if !context.args.concise {
+            if timing == Timing::Detailed {
+                eprintln!("Timing is detailed, with DNS, connect and TLS times from a separate probe connection.");
+            } else {
+                eprintln!("Timing is {}.", timing);
+            }
+        }
+        return Ok(true);
+    }
+
+    // Handle \async command
+    if parse_no_args_command(command, "async") {
+        context.async_next = true;
+        if !context.args.concise {
+            eprintln!("The next statement will run asynchronously.");
+        }
+        return Ok(true);
+    }
+
+    // Handle \x command
+    if let Some(mode) = parse_expanded(command) {
+        let expanded = match mode {
+            Some(mode) => Expanded::parse(&mode).ok_or_else(|| format!("Invalid value '{}' for \\x: expected on, off or auto", mode))?,
+            None if Expanded::parse(&context.args.expanded) == Some(Expanded::On) => Expanded::Off,
+            None => Expanded::On,
+        };
+        context.args.expanded = expanded.to_string();
+        // PSQL results are requested losslessly while expanded display is on
+        context.update_url();
+        if !context.args.concise {
+            eprintln!("Expanded display is {}.", expanded);
+        }
+        return Ok(true);
+    }
+
+    // Handle \pset command
+    if let Some((name, value)) = parse_pset(command) {
+        match name {
+            None => {
+                for name in PRINT_OPTION_NAMES {
+                    println!("{:<14} {}", name, context.print_options.get(name).unwrap_or_default());
+                }
+                println!("{:<14} {}", "expanded", Expanded::parse(&context.args.expanded).unwrap_or(Expanded::Off));
+            }
+            Some(name) if name == "expanded" => {
+                return Box::pin(handle_meta_command(context, &format!("\\x {}", value.unwrap_or_default()))).await;
+            }
+            Some(name) => {
+                context.print_options.set(&name, value.as_deref())?;
+                // Also kept with the arguments, which decide whether results are rendered locally. An earlier setting
+                // of the same option is replaced.
+                let prefix = format!("{}=", name);
+                context.args.pset.retain(|setting| !setting.starts_with(&prefix));
+                context.args.pset.push(format!("{}={}", name, context.print_options.get(&name).unwrap_or_default()));
+                context.update_url();
+                if !context.args.concise {
+                    eprintln!("{} is \"{}\".", name, context.print_options.get(&name).unwrap_or_default());
+                }
+            }
+        }
+        return Ok(true);
+    }
+
+    // Handle \last command
+    if let Some(format) = parse_last(command) {
+        let text = match format {
+            Some(name) => render(last_result(context)?, parse_format(&name)?, &context.print_options),
+            None => {
+                let format = Format::for_session(&context.args.format).unwrap_or(Format::Psql);
+                let expanded = Expanded::parse(&context.args.expanded).unwrap_or(Expanded::Off);
+                render_display(last_result(context)?, format, expanded, &context.print_options)
+            }
+        };
+        context.write_output(&text)?;
+        return Ok(true);
+    }
+
+    // Handle \plot command
+    if let Some(spec) = parse_plot(command) {
+        let text = plot(last_result(context)?, &PlotSpec::parse(&spec), terminal_width())?;
+        context.write_output(&text)?;
+        return Ok(true);
+    }
+
+    // Handle \summarize command
+    if let Some(columns) = parse_summarize(command) {
+        let summary = summarize(last_result(context)?, &columns)?;
+        let format = Format::for_session(&context.args.format).unwrap_or(Format::Psql);
+        let format = if format == Format::JsonLines { Format::Psql } else { format };
+        let expanded = Expanded::parse(&context.args.expanded).unwrap_or(Expanded::Off);
+        let text = render_display(&summary, format, expanded, &context.print_options);
+        context.write_output(&text)?;
+        return Ok(true);
+    }
+
+    // Handle \export command
+    if let Some((format, file)) = parse_export(command) {
+        let text = render(last_result(context)?, parse_format(&format)?, &context.print_options);
+        fs::write(&file, text).map_err(|e| format!("Cannot write {}: {}", file, e))?;
+        return Ok(true);
+    }
+
+    // Handle \o command
+    if let Some(file) = parse_output(command) {
+        context.output_file = match file {
+            Some(file) => {
+                let handle = fs::File::create(&file).map_err(|e| format!("Cannot open {}: {}", file, e))?;
+                Some((PathBuf::from(file), handle))
+            }
+            None => None,
+        };
+        return Ok(true);
+    }
+
+    // Handle \echo, \qecho and \warn commands
+    if let Some((target, arguments)) = parse_echo(command) {
+        let (text, newline) = echo_text(&arguments, &context.variables);
+        let text = if newline { text + "\n" } else { text };
+        match target.as_str() {
+            "echo" => {
+                print!("{}", text);
+                std::io::stdout().flush()?;
+            }
+            "qecho" => context.write_output(&text)?,
+            _ => eprint!("{}", text),
+        }
+        return Ok(true);
+    }
+
+    // Handle \if, \elif, \else and \endif commands
+    if let Some((keyword, expression)) = parse_conditional(command) {
+        context.conditionals.apply(&keyword, &expression, &context.variables)?;
+        return Ok(true);
+    }
+
+    // Handle \g and \gx commands
+    if let Some((vertical, file)) = parse_execute(command) {
+        let queries = take_buffered_queries(context)?;
+        let format = context.args.format.clone();
+        if vertical {
+            context.args.format = String::from("Vertical");
+            context.update_url();
+        }
+
+        let result = run_buffered_queries(context, queries, file.as_deref()).await;
+
+        if vertical {
+            context.args.format = format;
+            context.update_url();
+        }
+        result?;
+        return Ok(true);
+    }
+
+    // Handle \gset command
+    if let Some(prefix) = parse_gset(command) {
+        let queries = take_buffered_queries(context)?;
+        if queries.len() != 1 {
+            return Err("\\gset expects a single query".into());
+        }
+
+        let body = capture_tsv(context, queries.into_iter().next().unwrap_or_default()).await?;
+        for (name, value) in parse_single_row(&body)? {
+            let name = format!("{}{}", prefix.as_deref().unwrap_or_default(), name);
+            match value {
+                Some(value) => context.variables.insert(name, value),
+                None => context.variables.remove(&name),
+            };
+        }
+        return Ok(true);
+    }
+
+    // Handle \d, \dt, \dv, \di, \l and \de commands
+    if let Some((name, verbose, argument)) = parse_describe(command) {
+        describe(context, &name, verbose, argument.as_deref()).await?;
+        return Ok(true);
+    }
+
+    // Handle \c and \connect commands
+    if let Some(arguments) = parse_connect(command) {
+        connect(context, parse_connect_args(&arguments)?).await?;
+        return Ok(true);
+    }
+
+    // Handle \! command
+    if let Some(shell_command) = parse_shell(command) {
+        let status = run_shell(shell_command.as_deref())?;
+        if !status.success() {
+            eprintln!("Command exited with {}", status);
+        }
+        return Ok(true);
+    }
+
+    // Handle \setenv command
+    if let Some((name, value)) = parse_setenv(command) {
+        match value {
+            Some(value) => std::env::set_var(name, value),
+            None => std::env::remove_var(name),
+        }
+        return Ok(true);
+    }
+
+    // Handle \cd command
+    if let Some(dir) = parse_cd(command) {
+        let dir = match dir {
+            Some(dir) => PathBuf::from(dir),
+            None => dirs::home_dir().ok_or("Failed to get home directory")?,
+        };
+        std::env::set_current_dir(&dir).map_err(|e| format!("Cannot change directory to {}: {}", dir.display(), e))?;
+        return Ok(true);
+    }
+
+    // Handle \conninfo command
+    if parse_no_args_command(command, "conninfo") {
+        print_conninfo(context);
+        return Ok(true);
+    }
+
+    // Handle \settings command
+    if parse_no_args_command(command, "settings") {
+        print_settings(context);
+        return Ok(true);
+    }
+
     Ok(false)
 }
 
+// Edit the current buffer, or the last query when it is empty; the saved text becomes the pending input.
+fn edit_query(context: &mut Context, editor: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let text = if context.query_buffer.trim().is_empty() {
+        context.last_query.clone().unwrap_or_default()
+    } else {
+        context.query_buffer.clone()
+    };
+    let edited = edit_text(&text, editor)?;
+    context.query_buffer.clear();
+    context.pending_input = Some(edited.trim_end().to_string());
+    Ok(())
+}
+
 // Generic function to parse \set PROMPT command
 fn parse_set_prompt(command: &str, prompt_type: &str) -> Option<String> {
     static SET_PROMPT_RE: Lazy<Regex> = Lazy::new(|| {
@@ -83,112 +724,478 @@ fn parse_unset_prompt(command: &str, prompt_type: &str) -> bool {
     false
 }
 
+// Parse \? command, returns the optional command to show details for
+fn parse_help(command: &str) -> Option<Option<String>> {
+    static HELP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\\?(?:\s+(\S+))?\s*$"#).unwrap());
+
+    let captures = HELP_RE.captures(command)?;
+    Some(captures.get(1).map(|m| m.as_str().to_string()))
+}
+
+fn last_result(context: &Context) -> Result<&crate::result::ResultSet, Box<dyn std::error::Error>> {
+    context.last_result.as_ref().ok_or_else(|| {
+        "No result to show. Results in PSQL, Vertical and JSONLines_Compact format are kept up to --max-result-memory.".into()
+    })
+}
+
+fn parse_format(name: &str) -> Result<Format, Box<dyn std::error::Error>> {
+    Format::parse(name).ok_or_else(|| format!("Unknown format '{}'. Available: {}", name, FORMAT_NAMES).into())
+}
+
+// Parse \pset command, returns the optional option name and value
+fn parse_pset(command: &str) -> Option<(Option<String>, Option<String>)> {
+    static PSET_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"^\s*\\pset(?:\s+(\w+)(?:\s+(?:'((?:[^']|'')*)'|"([^"]*)"|(\S+)))?)?\s*$"#).unwrap()
+    });
+
+    let captures = PSET_RE.captures(command)?;
+    let value = match captures.get(2) {
+        Some(quoted) => Some(quoted.as_str().replace("''", "'")),
+        None => captures.get(3).or(captures.get(4)).map(|m| m.as_str().to_string()),
+    };
+    Some((captures.get(1).map(|m| m.as_str().to_lowercase()), value))
+}
+
+// Parse \timing command, returns the optional mode
+fn parse_timing(command: &str) -> Option<Option<String>> {
+    static TIMING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\timing(?:\s+(\S+))?\s*$"#).unwrap());
+
+    let captures = TIMING_RE.captures(command)?;
+    Some(captures.get(1).map(|m| m.as_str().to_string()))
+}
+
+// Parse \x command, returns the optional mode
+fn parse_expanded(command: &str) -> Option<Option<String>> {
+    static EXPANDED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\x(?:\s+(\S+))?\s*$"#).unwrap());
+
+    let captures = EXPANDED_RE.captures(command)?;
+    Some(captures.get(1).map(|m| m.as_str().to_string()))
+}
+
+// Parse \last command, returns the optional format
+fn parse_last(command: &str) -> Option<Option<String>> {
+    static LAST_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\last(?:\s+(\S+))?\s*$"#).unwrap());
+
+    let captures = LAST_RE.captures(command)?;
+    Some(captures.get(1).map(|m| m.as_str().to_string()))
+}
+
+// Parse \plot command, returns the chart kind and columns as written
+fn parse_plot(command: &str) -> Option<String> {
+    static PLOT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\plot(?:\s+(.*?))?\s*$"#).unwrap());
+
+    let captures = PLOT_RE.captures(command)?;
+    Some(captures.get(1).map_or("", |m| m.as_str()).to_string())
+}
+
+// Parse \summarize command, returns the column names
+fn parse_summarize(command: &str) -> Option<Vec<String>> {
+    static SUMMARIZE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\summarize(?:\s+(.*?))?\s*$"#).unwrap());
+
+    let captures = SUMMARIZE_RE.captures(command)?;
+    Some(captures.get(1).map_or(vec![], |m| m.as_str().split_whitespace().map(String::from).collect()))
+}
+
+// Parse \export command, returns the format and file
+fn parse_export(command: &str) -> Option<(String, String)> {
+    static EXPORT_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"^\s*\\export\s+(\S+)\s+(?:'([^']*)'|"([^"]*)"|(\S+))\s*$"#).unwrap()
+    });
+
+    let captures = EXPORT_RE.captures(command)?;
+    let file = captures.get(2).or(captures.get(3)).or(captures.get(4))?.as_str().to_string();
+    Some((captures[1].to_string(), file))
+}
+
+// Parse \o command, returns the optional output file
+fn parse_output(command: &str) -> Option<Option<String>> {
+    static OUTPUT_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"^\s*\\(?:o|out)(?:\s+(?:'([^']*)'|"([^"]*)"|(\S+)))?\s*$"#).unwrap()
+    });
+
+    let captures = OUTPUT_RE.captures(command)?;
+    Some(captures.get(1).or(captures.get(2)).or(captures.get(3)).map(|m| m.as_str().to_string()))
+}
+
+// Parse \echo, \qecho and \warn commands, returns the command name and raw arguments
+fn parse_echo(command: &str) -> Option<(String, String)> {
+    static ECHO_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\(echo|qecho|warn)(?:\s+(.*?))?\s*$"#).unwrap());
+
+    let captures = ECHO_RE.captures(command)?;
+    Some((captures[1].to_string(), captures.get(2).map_or("", |m| m.as_str()).to_string()))
+}
+
+// Join \echo arguments with spaces; quoted arguments are unquoted. Variables are substituted outside quotes only, after
+// the arguments are split, so values are printed as they are. Returns the text and whether a newline follows.
+fn echo_text(arguments: &str, variables: &BTreeMap<String, String>) -> (String, bool) {
+    let mut words: Vec<String> = vec![];
+    let mut newline = true;
+    let mut chars = arguments.trim().chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut word = String::new();
+        // Unquoted text not yet interpolated
+        let mut unquoted = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            // :'name' and :"name" are variable references rather than quotes
+            if c == ':' && matches!(chars.peek(), Some('\'' | '"')) {
+                let quote = chars.next().unwrap_or_default();
+                unquoted.push(c);
+                unquoted.push(quote);
+                for c in chars.by_ref() {
+                    unquoted.push(c);
+                    if c == quote {
+                        break;
+                    }
+                }
+                continue;
+            }
+            if c != '\'' {
+                unquoted.push(c);
+                continue;
+            }
+            word.push_str(&interpolate_variables(&std::mem::take(&mut unquoted), variables));
+            // Quoted part: '' is a quote, \n and \t are escapes
+            quoted = true;
+            while let Some(c) = chars.next() {
+                match c {
+                    '\'' if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                        word.push('\'');
+                    }
+                    '\'' => break,
+                    '\\' => match chars.next() {
+                        Some('n') => word.push('\n'),
+                        Some('t') => word.push('\t'),
+                        Some(other) => word.push(other),
+                        None => word.push('\\'),
+                    },
+                    _ => word.push(c),
+                }
+            }
+        }
+        word.push_str(&interpolate_variables(&unquoted, variables));
+        // Only a leading unquoted -n is the flag
+        if words.is_empty() && newline && !quoted && word == "-n" {
+            newline = false;
+            continue;
+        }
+        words.push(word);
+    }
+
+    (words.join(" "), newline)
+}
+
+// Parse \if, \elif, \else and \endif commands, returns the keyword and expression
+fn parse_conditional(command: &str) -> Option<(String, String)> {
+    static CONDITIONAL_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"^\s*\\(if|elif|else|endif)(?:\s+(.*?))?\s*$"#).unwrap());
+
+    let captures = CONDITIONAL_RE.captures(command)?;
+    let expression = captures.get(2).map_or("", |m| m.as_str());
+    let keyword = &captures[1];
+    if (keyword == "else" || keyword == "endif") && !expression.is_empty() {
+        return None;
+    }
+    Some((keyword.to_string(), expression.to_string()))
+}
+
+// Conditional commands are processed even inside skipped branches.
+pub fn is_conditional_command(command: &str) -> bool {
+    parse_conditional(command).is_some()
+}
+
+// Statements run by \g and friends: the query buffer, or the last query when the buffer is empty.
+fn take_buffered_queries(context: &mut Context) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut text = std::mem::take(&mut context.query_buffer).trim().to_string();
+    if text.is_empty() {
+        text = context.last_query.clone().ok_or("Query buffer is empty")?;
+    }
+    context.last_query = Some(text.clone());
+
+    if !text.ends_with(';') {
+        text += "\n;";
+    }
+    try_split_queries(&text).ok_or_else(|| "Query buffer does not contain a complete statement".into())
+}
+
+// Run statements, printing results or writing them to a file.
+async fn run_buffered_queries(context: &mut Context, queries: Vec<String>, file: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(file) = file else {
+        for q in queries {
+            run_statement(context, q).await?;
+        }
+        return Ok(());
+    };
+
+    let mut output = String::new();
+    for q in queries {
+        output += &capture_query(context, q).await?;
+    }
+    fs::write(file, output).map_err(|e| format!("Cannot write {}: {}", file, e))?;
+    Ok(())
+}
+
+// Column names and values of a result row; NULL is None.
+pub(crate) type Row = Vec<(String, Option<String>)>;
+
+// Parse a TabSeparatedWithNames result with exactly one row.
+pub(crate) fn parse_single_row(body: &str) -> Result<Row, Box<dyn std::error::Error>> {
+    let mut lines = body.lines().filter(|line| !line.is_empty());
+    let header = lines.next().ok_or("Query returned no columns")?;
+    let rows: Vec<&str> = lines.collect();
+    match rows.len() {
+        0 => return Err("Query returned no rows".into()),
+        1 => {}
+        _ => return Err("Query returned more than one row".into()),
+    }
+
+    let unescape = |value: &str| -> Option<String> {
+        if value == "\\N" {
+            return None;
+        }
+        let mut result = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('t') => result.push('\t'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('0') => result.push('\0'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        }
+        Some(result)
+    };
+
+    let names = header.split('\t').map(|name| unescape(name).unwrap_or_default());
+    let values = rows[0].split('\t').map(unescape);
+    Ok(names.zip(values).collect())
+}
+
+// Parse \g and \gx commands, returns whether output is vertical and the optional output file
+fn parse_execute(command: &str) -> Option<(bool, Option<String>)> {
+    static EXECUTE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"^\s*\\g(x)?(?:\s+(?:'([^']*)'|"([^"]*)"|(\S+)))?\s*$"#).unwrap()
+    });
+
+    let captures = EXECUTE_RE.captures(command)?;
+    let file = captures.get(2).or(captures.get(3)).or(captures.get(4)).map(|m| m.as_str().to_string());
+    Some((captures.get(1).is_some(), file))
+}
+
+// Parse \gset command, returns the optional variable prefix
+fn parse_gset(command: &str) -> Option<Option<String>> {
+    static GSET_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\gset(?:\s+(\w+))?\s*$"#).unwrap());
+
+    let captures = GSET_RE.captures(command)?;
+    Some(captures.get(1).map(|m| m.as_str().to_string()))
+}
+
+// Parse \! command, returns the optional shell command
+fn parse_shell(command: &str) -> Option<Option<String>> {
+    static SHELL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\!\s*(.*?)\s*$"#).unwrap());
+
+    let captures = SHELL_RE.captures(command)?;
+    let shell_command = &captures[1];
+    Some(if shell_command.is_empty() { None } else { Some(shell_command.to_string()) })
+}
+
+// Parse \setenv command, returns the variable name and optional value
+fn parse_setenv(command: &str) -> Option<(String, Option<String>)> {
+    static SETENV_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"(?i)^\s*\\setenv\s+([^\s=]+)(?:\s+(?:'([^']*)'|"([^"]*)"|(.+?)))?\s*$"#).unwrap()
+    });
+
+    let captures = SETENV_RE.captures(command)?;
+    let value = captures.get(2).or(captures.get(3)).or(captures.get(4)).map(|m| m.as_str().to_string());
+    Some((captures[1].to_string(), value))
+}
+
+// Parse \cd command, returns the optional directory
+fn parse_cd(command: &str) -> Option<Option<String>> {
+    static CD_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"(?i)^\s*\\cd(?:\s+(?:'([^']*)'|"([^"]*)"|(\S+)))?\s*$"#).unwrap()
+    });
+
+    let captures = CD_RE.captures(command)?;
+    Some(captures.get(1).or(captures.get(2)).or(captures.get(3)).map(|m| m.as_str().to_string()))
+}
+
+// Parse \e command
+fn parse_edit(command: &str) -> bool {
+    parse_no_args_command(command, "e")
+}
+
+// Check whether command is the given meta-command without arguments
+fn parse_no_args_command(command: &str, name: &str) -> bool {
+    command.trim().strip_prefix('\\').is_some_and(|c| c.eq_ignore_ascii_case(name))
+}
+
+// Parse \ef command, returns the file to edit
+fn parse_edit_file(command: &str) -> Option<String> {
+    static EDIT_FILE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"(?i)^\s*\\ef\s+(?:'([^']*)'|"([^"]*)"|(\S+))\s*$"#).unwrap()
+    });
+
+    let captures = EDIT_FILE_RE.captures(command)?;
+    captures.get(1).or(captures.get(2)).or(captures.get(3)).map(|m| m.as_str().to_string())
+}
+
+// Parse \i and \ir commands, returns the file to include and whether it is relative to the current file
+fn parse_include(command: &str) -> Option<(String, bool)> {
+    static INCLUDE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"(?i)^\s*\\(i|ir|include|include_relative)\s+(?:'([^']*)'|"([^"]*)"|(\S+))\s*$"#).unwrap()
+    });
+
+    let captures = INCLUDE_RE.captures(command)?;
+    let relative = matches!(captures[1].to_lowercase().as_str(), "ir" | "include_relative");
+    let path = captures.get(2).or(captures.get(3)).or(captures.get(4))?.as_str().to_string();
+    Some((path, relative))
+}
+
+// Parse \d-family commands, returns the command name, whether "+" was given and the optional argument
+fn parse_describe(command: &str) -> Option<(String, bool, Option<String>)> {
+    static DESCRIBE_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"^\s*\\(d|dt|dv|di|de|l)(\+)?(?:\s+(\S+))?\s*$"#).unwrap());
+
+    let captures = DESCRIBE_RE.captures(command)?;
+    Some((
+        captures[1].to_string(),
+        captures.get(2).is_some(),
+        captures.get(3).map(|m| m.as_str().to_string()),
+    ))
+}
+
+// Parse \c and \connect commands, returns the arguments
+fn parse_connect(command: &str) -> Option<String> {
+    static CONNECT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*\\(?:c|connect)(?:\s+(.*?))?\s*$"#).unwrap());
+
+    let captures = CONNECT_RE.captures(command)?;
+    Some(captures.get(1).map_or(String::new(), |m| m.as_str().to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::args::get_args;
 
-    #[test]
-    fn test_set_prompt1_single_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt1_single_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT1 'custom_prompt> '"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt1, Some("custom_prompt> ".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt1_double_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt1_double_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT1 "custom_prompt> ""#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt1, Some("custom_prompt> ".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt1_no_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt1_no_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT1 custom_prompt>"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt1, Some("custom_prompt>".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt2_single_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt2_single_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT2 'custom_prompt> '"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt2, Some("custom_prompt> ".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt2_double_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt2_double_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT2 "custom_prompt> ""#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt2, Some("custom_prompt> ".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt2_no_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt2_no_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT2 custom_prompt>"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt2, Some("custom_prompt>".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt3_single_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt3_single_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT3 'custom_prompt> '"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt3, Some("custom_prompt> ".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt3_double_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt3_double_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT3 "custom_prompt> ""#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt3, Some("custom_prompt> ".to_string()));
     }
 
-    #[test]
-    fn test_set_prompt3_no_quotes() {
+    #[tokio::test]
+    async fn test_set_prompt3_no_quotes() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         let command = r#"\set PROMPT3 custom_prompt>"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt3, Some("custom_prompt>".to_string()));
     }
 
-    #[test]
-    fn test_unset_prompt1() {
+    #[tokio::test]
+    async fn test_unset_prompt1() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
@@ -198,13 +1205,13 @@ mod tests {
         
         // Then unset it
         let command = r#"\unset PROMPT1"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt1, None);
     }
 
-    #[test]
-    fn test_unset_prompt2() {
+    #[tokio::test]
+    async fn test_unset_prompt2() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
@@ -214,13 +1221,13 @@ mod tests {
         
         // Then unset it
         let command = r#"\unset PROMPT2"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt2, None);
     }
 
-    #[test]
-    fn test_unset_prompt3() {
+    #[tokio::test]
+    async fn test_unset_prompt3() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
@@ -230,40 +1237,40 @@ mod tests {
         
         // Then unset it
         let command = r#"\unset PROMPT3"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt3, None);
     }
 
-    #[test]
-    fn test_invalid_commands() {
+    #[tokio::test]
+    async fn test_invalid_commands() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         // Invalid commands should return false
         let command = r#"\invalid command"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(!result);
         
         let command = r#"\set INVALID value"#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(!result);
     }
 
-    #[test]
-    fn test_whitespace_handling() {
+    #[tokio::test]
+    async fn test_whitespace_handling() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
         // Test with various whitespace
         let command = r#"  \set  PROMPT1  'test>'  "#;
-        let result = handle_meta_command(&mut context, command).unwrap();
+        let result = handle_meta_command(&mut context, command).await.unwrap();
         assert!(result);
         assert_eq!(context.prompt1, Some("test>".to_string()));
     }
 
-    #[test]
-    fn test_prompt_independence() {
+    #[tokio::test]
+    async fn test_prompt_independence() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
@@ -272,9 +1279,9 @@ mod tests {
         let command2 = r#"\set PROMPT2 'prompt2> '"#;
         let command3 = r#"\set PROMPT3 'prompt3> '"#;
         
-        handle_meta_command(&mut context, command1).unwrap();
-        handle_meta_command(&mut context, command2).unwrap();
-        handle_meta_command(&mut context, command3).unwrap();
+        handle_meta_command(&mut context, command1).await.unwrap();
+        handle_meta_command(&mut context, command2).await.unwrap();
+        handle_meta_command(&mut context, command3).await.unwrap();
         
         // Verify all prompts are set independently
         assert_eq!(context.prompt1, Some("prompt1> ".to_string()));
@@ -283,7 +1290,7 @@ mod tests {
         
         // Unset only PROMPT2
         let unset_command = r#"\unset PROMPT2"#;
-        handle_meta_command(&mut context, unset_command).unwrap();
+        handle_meta_command(&mut context, unset_command).await.unwrap();
         
         // Verify only PROMPT2 was unset
         assert_eq!(context.prompt1, Some("prompt1> ".to_string()));
@@ -291,8 +1298,8 @@ mod tests {
         assert_eq!(context.prompt3, Some("prompt3> ".to_string()));
     }
 
-    #[test]
-    fn test_case_insensitive_prompt_types() {
+    #[tokio::test]
+    async fn test_case_insensitive_prompt_types() {
         let args = get_args().unwrap();
         let mut context = Context::new(args);
         
@@ -301,13 +1308,330 @@ mod tests {
         let command2 = r#"\set Prompt2 'test2> '"#;
         let command3 = r#"\set PROMPT3 'test3> '"#;
         
-        handle_meta_command(&mut context, command1).unwrap();
-        handle_meta_command(&mut context, command2).unwrap();
-        handle_meta_command(&mut context, command3).unwrap();
+        handle_meta_command(&mut context, command1).await.unwrap();
+        handle_meta_command(&mut context, command2).await.unwrap();
+        handle_meta_command(&mut context, command3).await.unwrap();
         
         // Verify all prompts are set correctly regardless of case
         assert_eq!(context.prompt1, Some("test1> ".to_string()));
         assert_eq!(context.prompt2, Some("test2> ".to_string()));
         assert_eq!(context.prompt3, Some("test3> ".to_string()));
     }
+
+    #[test]
+    fn test_parse_edit() {
+        assert!(parse_edit(r#"\e"#));
+        assert!(parse_edit(r#"  \e  "#));
+        assert!(!parse_edit(r#"\echo hi"#));
+        assert!(!parse_edit(r#"\ef file.sql"#));
+
+        assert_eq!(parse_edit_file(r#"\ef file.sql"#), Some("file.sql".to_string()));
+        assert_eq!(parse_edit_file(r#"\ef 'my file.sql'"#), Some("my file.sql".to_string()));
+        assert_eq!(parse_edit_file(r#"\ef"#), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_last_query() {
+        let args = get_args().unwrap();
+        let mut context = Context::new(args);
+        context.last_query = Some("SELECT 42;".to_string());
+
+        // `true` leaves the file untouched, so the edited text is the original one
+        edit_query(&mut context, "true").unwrap();
+        assert_eq!(context.pending_input, Some("SELECT 42;".to_string()));
+
+        // The current buffer takes precedence over the last query
+        context.query_buffer = "SELECT 1\nFROM t\n".to_string();
+        edit_query(&mut context, "true").unwrap();
+        assert_eq!(context.pending_input, Some("SELECT 1\nFROM t".to_string()));
+        assert!(context.query_buffer.is_empty());
+    }
+
+    #[test]
+    fn test_parse_describe() {
+        assert_eq!(parse_describe(r#"\d"#), Some(("d".to_string(), false, None)));
+        assert_eq!(parse_describe(r#"\d+ orders"#), Some(("d".to_string(), true, Some("orders".to_string()))));
+        assert_eq!(parse_describe(r#"\dt public.*"#), Some(("dt".to_string(), false, Some("public.*".to_string()))));
+        assert_eq!(parse_describe(r#"\l"#), Some(("l".to_string(), false, None)));
+        assert_eq!(parse_describe(r#"\de+"#), Some(("de".to_string(), true, None)));
+        assert_eq!(parse_describe(r#"\dx"#), None);
+        assert_eq!(parse_describe(r#"\d a b"#), None);
+    }
+
+    #[tokio::test]
+    async fn test_async() {
+        let mut context = Context::new(get_args().unwrap());
+        assert!(handle_meta_command(&mut context, r#"\async"#).await.unwrap());
+        assert!(context.async_next);
+        assert!(!handle_meta_command(&mut context, r#"\async now"#).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_help() {
+        let args = get_args().unwrap();
+        let mut context = Context::new(args);
+
+        assert!(handle_meta_command(&mut context, r#"\?"#).await.unwrap());
+        assert!(handle_meta_command(&mut context, r#"\? dt"#).await.unwrap());
+        assert!(handle_meta_command(&mut context, r#"\? \connect"#).await.unwrap());
+        assert!(handle_meta_command(&mut context, r#"\? nope"#).await.is_err());
+
+        assert_eq!(parse_help(r#"\?"#), Some(None));
+        assert_eq!(parse_help(r#"\? d+"#), Some(Some("d+".to_string())));
+        assert_eq!(find_meta_command("d+").unwrap().names, &["d"]);
+        assert_eq!(find_meta_command("\\include").unwrap().names, &["i", "include"]);
+    }
+
+    #[test]
+    fn test_unknown_command_message() {
+        let message = unknown_command_message(r#"\st PROMPT1"#);
+        assert!(message.starts_with(r#"Invalid command \st."#), "{}", message);
+        assert!(message.contains(r#"\set"#), "{}", message);
+
+        let message = unknown_command_message(r#"\conn"#);
+        assert!(message.contains(r#"\connect"#), "{}", message);
+        assert!(message.contains(r#"\conninfo"#), "{}", message);
+
+        let message = unknown_command_message(r#"\set INVALID value"#);
+        assert!(message.starts_with(r#"Invalid arguments for \set"#), "{}", message);
+
+        let message = unknown_command_message(r#"\zzzzzz"#);
+        assert_eq!(message, r#"Invalid command \zzzzzz. Try \? for help."#);
+
+        assert_eq!(edit_distance("st", "set"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_parse_shell_commands() {
+        assert_eq!(parse_shell(r#"\! ls -la | head"#), Some(Some("ls -la | head".to_string())));
+        assert_eq!(parse_shell(r#"\!"#), Some(None));
+        assert_eq!(parse_shell(r#"\set x"#), None);
+
+        assert_eq!(parse_setenv(r#"\setenv EDITOR nano"#), Some(("EDITOR".to_string(), Some("nano".to_string()))));
+        assert_eq!(parse_setenv(r#"\setenv PAGER 'less -S'"#), Some(("PAGER".to_string(), Some("less -S".to_string()))));
+        assert_eq!(parse_setenv(r#"\setenv EDITOR"#), Some(("EDITOR".to_string(), None)));
+        assert_eq!(parse_setenv(r#"\setenv"#), None);
+
+        assert_eq!(parse_cd(r#"\cd /tmp"#), Some(Some("/tmp".to_string())));
+        assert_eq!(parse_cd(r#"\cd"#), Some(None));
+        assert_eq!(parse_cd(r#"\c db"#), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_setenv_and_shell() {
+        let args = get_args().unwrap();
+        let mut context = Context::new(args);
+
+        let result = handle_meta_command(&mut context, r#"\setenv FB_TEST_SETENV 'some value'"#).await.unwrap();
+        assert!(result);
+        assert_eq!(std::env::var("FB_TEST_SETENV").unwrap(), "some value");
+
+        // Child processes see the variable
+        let result = handle_meta_command(&mut context, r#"\! test "$FB_TEST_SETENV" = "some value""#).await.unwrap();
+        assert!(result);
+
+        handle_meta_command(&mut context, r#"\setenv FB_TEST_SETENV"#).await.unwrap();
+        assert!(std::env::var("FB_TEST_SETENV").is_err());
+    }
+
+    #[test]
+    fn test_parse_execute_commands() {
+        assert_eq!(parse_execute(r#"\g"#), Some((false, None)));
+        assert_eq!(parse_execute(r#"\g out.txt"#), Some((false, Some("out.txt".to_string()))));
+        assert_eq!(parse_execute(r#"\gx 'my file.txt'"#), Some((true, Some("my file.txt".to_string()))));
+        assert_eq!(parse_execute(r#"\gset"#), None);
+
+        assert_eq!(parse_gset(r#"\gset"#), Some(None));
+        assert_eq!(parse_gset(r#"\gset max_"#), Some(Some("max_".to_string())));
+        assert_eq!(parse_gset(r#"\g"#), None);
+    }
+
+    #[test]
+    fn test_parse_single_row() {
+        let row = parse_single_row("max_id\tname\tnote\n42\tit\\ts\t\\N\n").unwrap();
+        assert_eq!(
+            row,
+            vec![
+                ("max_id".to_string(), Some("42".to_string())),
+                ("name".to_string(), Some("it\ts".to_string())),
+                ("note".to_string(), None),
+            ]
+        );
+
+        assert!(parse_single_row("max_id\n").unwrap_err().to_string().contains("no rows"));
+        assert!(parse_single_row("max_id\n1\n2\n").unwrap_err().to_string().contains("more than one row"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_buffer() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+
+        // \g without a buffer or previous query is an error
+        assert!(handle_meta_command(&mut context, r#"\g"#).await.is_err());
+
+        // Set statements are handled locally, so \g runs them without a server
+        context.query_buffer = "set a = 1".to_string();
+        assert!(handle_meta_command(&mut context, r#"\g"#).await.unwrap());
+        assert!(context.query_buffer.is_empty());
+        assert_eq!(context.last_query, Some("set a = 1".to_string()));
+        assert!(context.args.extra.iter().any(|e| e == "a=1"));
+
+        // \gx restores the session format
+        let format = context.args.format.clone();
+        context.query_buffer = "set b = 2;".to_string();
+        assert!(handle_meta_command(&mut context, r#"\gx"#).await.unwrap());
+        assert_eq!(context.args.format, format);
+        assert!(!context.url.contains("Vertical"));
+    }
+
+    #[test]
+    fn test_echo_text() {
+        let none = BTreeMap::new();
+        assert_eq!(echo_text("hello   world", &none), ("hello world".to_string(), true));
+        assert_eq!(echo_text("-n 'two  spaces' it''s", &none), ("two  spaces its".to_string(), false));
+        assert_eq!(echo_text("'it''s' 'a\\tb'", &none), ("it's a\tb".to_string(), true));
+        assert_eq!(echo_text("", &none), ("".to_string(), true));
+        assert_eq!(echo_text("'-n'", &none), ("-n".to_string(), true));
+
+        // Values are substituted after quotes are parsed, and not inside quotes
+        let variables = BTreeMap::from([("name".to_string(), "O'Brien  Jr".to_string())]);
+        assert_eq!(echo_text("hello :name!", &variables), ("hello O'Brien  Jr!".to_string(), true));
+        assert_eq!(echo_text("':name' x:name", &variables), (":name xO'Brien  Jr".to_string(), true));
+        assert_eq!(echo_text(":'name' :unknown", &variables), ("'O''Brien  Jr' :unknown".to_string(), true));
+
+        assert_eq!(parse_echo(r#"\echo -n done"#), Some(("echo".to_string(), "-n done".to_string())));
+        assert_eq!(parse_echo(r#"\warn"#), Some(("warn".to_string(), "".to_string())));
+        assert_eq!(parse_echo(r#"\echoes"#), None);
+    }
+
+    #[tokio::test]
+    async fn test_output_file_and_qecho() {
+        let path = std::env::temp_dir().join(format!("fb-output-{}.txt", std::process::id()));
+        let mut context = Context::new(get_args().unwrap());
+        context.variables.insert("step".to_string(), "2".to_string());
+
+        let command = format!(r#"\o '{}'"#, path.display());
+        assert!(handle_meta_command(&mut context, &command).await.unwrap());
+        assert!(handle_meta_command(&mut context, r#"\qecho step :step of 3"#).await.unwrap());
+        assert!(handle_meta_command(&mut context, r#"\qecho -n 'no newline'"#).await.unwrap());
+        assert!(handle_meta_command(&mut context, r#"\o"#).await.unwrap());
+        assert!(context.output_file.is_none());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "step 2 of 3\nno newline");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_last_and_export() {
+        let mut context = Context::new(get_args().unwrap());
+        assert!(handle_meta_command(&mut context, r#"\last"#).await.is_err());
+
+        context.last_result = crate::result::parse_response(concat!(
+            r#"{"message_type":"START","result_columns":[{"name":"id","type":"integer"}]}"#,
+            "\n",
+            r#"{"message_type":"DATA","data":[[1],[2]]}"#,
+        ))
+        .and_then(|response| match response {
+            crate::result::Response::Result(result) => Some(result),
+            _ => None,
+        });
+
+        let path = std::env::temp_dir().join(format!("fb-export-{}.csv", std::process::id()));
+        let command = format!(r#"\export csv '{}'"#, path.display());
+        assert!(handle_meta_command(&mut context, &command).await.unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "id\r\n1\r\n2\r\n");
+        fs::remove_file(&path).unwrap();
+
+        assert!(handle_meta_command(&mut context, r#"\last vertical"#).await.unwrap());
+        let error = handle_meta_command(&mut context, r#"\last xml"#).await.unwrap_err().to_string();
+        assert!(error.contains("Unknown format 'xml'"), "{}", error);
+
+        assert_eq!(parse_export(r#"\export json"#), None);
+        assert_eq!(parse_last(r#"\lastx"#), None);
+    }
+
+    #[tokio::test]
+    async fn test_plot() {
+        assert_eq!(parse_plot(r#"\plot"#), Some(String::new()));
+        assert_eq!(parse_plot(r#"\plot bar day  total "#), Some(String::from("bar day  total")));
+        assert_eq!(parse_plot(r#"\plots"#), None);
+
+        let mut context = Context::new(get_args().unwrap());
+        context.last_result = None;
+        assert!(handle_meta_command(&mut context, r#"\plot"#).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_summarize() {
+        assert_eq!(parse_summarize(r#"\summarize"#), Some(vec![]));
+        assert_eq!(parse_summarize(r#"\summarize id  name"#), Some(vec![String::from("id"), String::from("name")]));
+        assert_eq!(parse_summarize(r#"\summary"#), None);
+
+        let mut context = Context::new(get_args().unwrap());
+        context.last_result = None;
+        assert!(handle_meta_command(&mut context, r#"\summarize"#).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timing_toggle() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        context.args.timing = String::new();
+
+        assert!(handle_meta_command(&mut context, r#"\timing"#).await.unwrap());
+        assert_eq!(context.args.timing, "off");
+        assert!(handle_meta_command(&mut context, r#"\timing"#).await.unwrap());
+        assert_eq!(context.args.timing, "on");
+        assert!(handle_meta_command(&mut context, r#"\timing detailed"#).await.unwrap());
+        assert_eq!(context.args.timing, "detailed");
+        assert!(handle_meta_command(&mut context, r#"\timing verbose"#).await.is_err());
+        assert_eq!(parse_timing(r#"\timings"#), None);
+    }
+
+    #[tokio::test]
+    async fn test_expanded_toggle() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        context.args.expanded = String::new();
+
+        assert!(handle_meta_command(&mut context, r#"\x"#).await.unwrap());
+        assert_eq!(context.args.expanded, "on");
+        assert!(handle_meta_command(&mut context, r#"\x"#).await.unwrap());
+        assert_eq!(context.args.expanded, "off");
+        assert!(handle_meta_command(&mut context, r#"\x auto"#).await.unwrap());
+        assert_eq!(context.args.expanded, "auto");
+        assert!(handle_meta_command(&mut context, r#"\x sideways"#).await.is_err());
+        assert_eq!(context.args.expanded, "auto");
+        // The format requested from the server is unchanged
+        assert!(!context.url.contains("Vertical"));
+    }
+
+    #[tokio::test]
+    async fn test_pset() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+
+        assert_eq!(parse_pset(r#"\pset"#), Some((None, None)));
+        assert_eq!(parse_pset(r#"\pset null '(null)'"#), Some((Some("null".to_string()), Some("(null)".to_string()))));
+        assert_eq!(parse_pset(r#"\pset null ''"#), Some((Some("null".to_string()), Some("".to_string()))));
+        assert_eq!(parse_pset(r#"\pset Footer"#), Some((Some("footer".to_string()), None)));
+
+        assert!(handle_meta_command(&mut context, r#"\pset null ∅"#).await.unwrap());
+        assert_eq!(context.print_options.null, "∅");
+        assert!(handle_meta_command(&mut context, r#"\pset linestyle unicode"#).await.unwrap());
+        assert_eq!(context.print_options.get("linestyle"), Some("unicode".to_string()));
+        assert!(handle_meta_command(&mut context, r#"\pset null '(null)'"#).await.unwrap());
+        assert_eq!(context.args.pset.iter().filter(|p| p.starts_with("null=")).collect::<Vec<_>>(), vec!["null=(null)"]);
+        let footer = context.print_options.footer;
+        assert!(handle_meta_command(&mut context, r#"\pset footer"#).await.unwrap());
+        assert_eq!(context.print_options.footer, !footer);
+        assert!(handle_meta_command(&mut context, r#"\pset expanded on"#).await.unwrap());
+        assert_eq!(context.args.expanded, "on");
+        assert!(handle_meta_command(&mut context, r#"\pset border 5"#).await.is_err());
+        assert!(handle_meta_command(&mut context, r#"\pset"#).await.unwrap());
+    }
 }
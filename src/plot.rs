@@ -0,0 +1,434 @@
+use console::{measure_text_width, truncate_str, Term};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::render::{is_numeric_type, value_text};
+use crate::result::ResultSet;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartKind {
+    Line,
+    Bar,
+    Sparkline,
+}
+
+// Chart requested with \plot or --plot: an optional kind followed by [x_col] y_col...
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlotSpec {
+    pub kind: ChartKind,
+    pub columns: Vec<String>,
+}
+
+// Rows of the line chart plot area.
+const LINE_HEIGHT: usize = 12;
+// Point markers of the line chart series, in legend order.
+const MARKERS: [char; 6] = ['*', '+', 'o', 'x', '#', '@'];
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Partial bar blocks by eighths; index 0 is no block.
+const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+struct Series {
+    name: String,
+    values: Vec<Option<f64>>,
+}
+
+impl PlotSpec {
+    // Parse "[line|bar|spark] [x_col] y_col...". With two or more columns the first one is the x axis.
+    pub fn parse(text: &str) -> Self {
+        let mut columns: Vec<String> = text.split_whitespace().map(String::from).collect();
+        let kind = match columns.first().map(|c| c.to_lowercase()).as_deref() {
+            Some("line") => Some(ChartKind::Line),
+            Some("bar") => Some(ChartKind::Bar),
+            Some("spark") | Some("sparkline") => Some(ChartKind::Sparkline),
+            _ => None,
+        };
+        if kind.is_some() {
+            columns.remove(0);
+        }
+
+        Self { kind: kind.unwrap_or(ChartKind::Line), columns }
+    }
+}
+
+// Width of the terminal, 80 when stdout is not one.
+pub fn terminal_width() -> usize {
+    Term::stdout().size_checked().map_or(80, |(_, columns)| columns as usize)
+}
+
+fn column_index(result: &ResultSet, name: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    result.columns.iter().position(|c| c.name == name).ok_or_else(|| {
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        format!("Column '{}' not found in result; columns are: {}", name, names.join(", ")).into()
+    })
+}
+
+pub(crate) fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        // Large integers and decimals may come as strings
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_time_type(type_name: &str) -> bool {
+    let type_name = type_name.to_lowercase();
+    type_name.contains("date") || type_name.contains("timestamp")
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// Seconds since the epoch of a date or timestamp such as 2024-02-07 01:19:19.81689+00.
+fn parse_time(text: &str) -> Option<f64> {
+    static TIME_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(\d{4})-(\d{2})-(\d{2})(?:[ T](\d{2}):(\d{2})(?::(\d{2}(?:\.\d+)?))?)?\s*(Z|[+-]\d{2}(?::?\d{2})?)?$").unwrap()
+    });
+
+    let captures = TIME_RE.captures(text.trim())?;
+    let part = |i: usize| captures.get(i).map_or(0, |m| m.as_str().parse::<i64>().unwrap_or(0));
+    let seconds: f64 = captures.get(6).map_or(0.0, |m| m.as_str().parse().unwrap_or(0.0));
+    let offset = match captures.get(7).map(|m| m.as_str()) {
+        None | Some("Z") => 0,
+        Some(zone) => {
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            let digits: String = zone[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i64 = digits[..2].parse().ok()?;
+            let minutes: i64 = digits.get(2..).filter(|m| !m.is_empty()).map_or(Some(0), |m| m.parse().ok())?;
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    let days = days_from_civil(part(1), part(2), part(3));
+    Some((days * 86400 + part(4) * 3600 + part(5) * 60 - offset) as f64 + seconds)
+}
+
+pub(crate) fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let text = format!("{:.2}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+// Pick the x axis and series. Without columns the first column is the x axis unless it is numeric,
+// and all numeric columns are plotted.
+fn select_columns(result: &ResultSet, spec: &PlotSpec) -> Result<(Option<usize>, Vec<usize>), Box<dyn std::error::Error>> {
+    match spec.columns.as_slice() {
+        [] => {
+            let numeric: Vec<usize> = (0..result.columns.len()).filter(|&i| is_numeric_type(&result.columns[i].type_name)).collect();
+            let x = Some(0).filter(|_| result.columns.len() > 1 && !numeric.contains(&0));
+            if numeric.is_empty() {
+                return Err("No numeric columns to plot; name them with \\plot [x_col] y_col...".into());
+            }
+            Ok((x, numeric))
+        }
+        [y] => Ok((None, vec![column_index(result, y)?])),
+        [x, ys @ ..] => {
+            let ys = ys.iter().map(|y| column_index(result, y)).collect::<Result<Vec<_>, _>>()?;
+            Ok((Some(column_index(result, x)?), ys))
+        }
+    }
+}
+
+// Render the result as a chart that fits in `width` columns.
+pub fn plot(result: &ResultSet, spec: &PlotSpec, width: usize) -> Result<String, Box<dyn std::error::Error>> {
+    let (x, ys) = select_columns(result, spec)?;
+    if result.rows.is_empty() {
+        return Err("The result has no rows to plot".into());
+    }
+
+    let mut series = vec![];
+    for y in ys {
+        let values: Vec<Option<f64>> = result.rows.iter().map(|row| row.get(y).and_then(number)).collect();
+        if values.iter().all(Option::is_none) {
+            return Err(format!("Column '{}' has no numeric values to plot", result.columns[y].name).into());
+        }
+        series.push(Series { name: result.columns[y].name.clone(), values });
+    }
+
+    let labels: Vec<String> = match x {
+        Some(x) => result.rows.iter().map(|row| row.get(x).map(value_text).unwrap_or_default()).collect(),
+        None => (1..=result.rows.len()).map(|i| i.to_string()).collect(),
+    };
+
+    let text = match spec.kind {
+        ChartKind::Line => {
+            // Time and numeric axes are spaced by value, others by row
+            let positions: Option<Vec<f64>> = match x {
+                Some(x) if is_time_type(&result.columns[x].type_name) => labels.iter().map(|l| parse_time(l)).collect(),
+                Some(x) if is_numeric_type(&result.columns[x].type_name) => {
+                    result.rows.iter().map(|row| row.get(x).and_then(number)).collect()
+                }
+                _ => None,
+            };
+            let positions = positions.unwrap_or_else(|| (0..labels.len()).map(|i| i as f64).collect());
+            render_line(&labels, &positions, &series, width)
+        }
+        ChartKind::Bar => render_bar(&labels, &series, width),
+        ChartKind::Sparkline => render_sparkline(&series, width),
+    };
+
+    Ok(text)
+}
+
+fn value_range(series: &[Series]) -> (f64, f64) {
+    let values = series.iter().flat_map(|s| s.values.iter().flatten());
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| (min.min(v), max.max(v)));
+    if min == max {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    }
+}
+
+fn render_line(labels: &[String], positions: &[f64], series: &[Series], width: usize) -> String {
+    let (min, max) = value_range(series);
+    let height = LINE_HEIGHT;
+    let middle = height / 2;
+    let middle_value = max - (max - min) * middle as f64 / (height - 1) as f64;
+    let axis_labels = [format_number(max), format_number(middle_value), format_number(min)];
+    let label_width = axis_labels.iter().map(|l| l.len()).max().unwrap_or(0);
+    let plot_width = width.saturating_sub(label_width + 2).max(10);
+
+    let (first, last) = positions.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), &p| (a.min(p), b.max(p)));
+    let column = |position: f64| {
+        if last > first {
+            (position - first) / (last - first) * (plot_width - 1) as f64
+        } else {
+            (plot_width / 2) as f64
+        }
+    };
+    let row = |value: f64| (((max - value) / (max - min)) * (height - 1) as f64).round() as usize;
+
+    let mut grid = vec![vec![' '; plot_width]; height];
+    for (s, marker) in series.iter().zip(MARKERS.iter().cycle()) {
+        let mut points: Vec<(f64, f64)> =
+            s.values.iter().zip(positions).filter_map(|(value, &position)| value.map(|v| (column(position), v))).collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (i, &(x, y)) in points.iter().enumerate() {
+            grid[row(y)][x.round() as usize] = *marker;
+            // Connect to the next point by interpolating over the columns in between
+            if let Some(&(next_x, next_y)) = points.get(i + 1) {
+                let columns = x.ceil() as usize..=(next_x.floor() as usize).min(plot_width - 1);
+                let cells = columns.map(|c| {
+                    let t = if next_x > x { (c as f64 - x) / (next_x - x) } else { 0.0 };
+                    (row(y + (next_y - y) * t), c)
+                });
+                for (r, c) in cells {
+                    grid[r][c] = *marker;
+                }
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for (r, cells) in grid.iter().enumerate() {
+        let (label, tick) = match r {
+            0 => (axis_labels[0].as_str(), '┤'),
+            r if r == middle => (axis_labels[1].as_str(), '┤'),
+            r if r == height - 1 => (axis_labels[2].as_str(), '┤'),
+            _ => ("", '│'),
+        };
+        let line = format!("{:>label_width$} {}{}", label, tick, cells.iter().collect::<String>());
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output.push_str(&format!("{:label_width$} └{}\n", "", "─".repeat(plot_width)));
+
+    // First and last x labels under the ends of the axis
+    let first_label = labels.first().map_or("", String::as_str);
+    let last_label = labels.last().map_or("", String::as_str);
+    let gap = plot_width.saturating_sub(measure_text_width(first_label) + measure_text_width(last_label));
+    let axis = if labels.len() > 1 && gap > 0 {
+        format!("{}{}{}", first_label, " ".repeat(gap), last_label)
+    } else {
+        first_label.to_string()
+    };
+    output.push_str(&format!("{:label_width$}  {}\n", "", axis));
+
+    let legend: Vec<String> = series.iter().zip(MARKERS.iter().cycle()).map(|(s, m)| format!("{} {}", m, s.name)).collect();
+    output.push_str(&format!("{:label_width$}  {}\n", "", legend.join("  ")));
+    output
+}
+
+fn render_bar(labels: &[String], series: &[Series], width: usize) -> String {
+    let label_width = labels.iter().map(|l| measure_text_width(l)).max().unwrap_or(0).min(width / 3);
+    let name_width = if series.len() > 1 { series.iter().map(|s| measure_text_width(&s.name)).max().unwrap_or(0) } else { 0 };
+    let value_texts: Vec<Vec<String>> =
+        series.iter().map(|s| s.values.iter().map(|v| v.map(format_number).unwrap_or_default()).collect()).collect();
+    let value_width = value_texts.iter().flatten().map(|v| v.len()).max().unwrap_or(0);
+    let bar_width = width.saturating_sub(label_width + name_width + value_width + 4).max(10);
+    let max = series.iter().flat_map(|s| s.values.iter().flatten()).fold(0.0_f64, |max, &v| max.max(v));
+
+    let mut output = String::new();
+    for (r, label) in labels.iter().enumerate() {
+        for (i, s) in series.iter().enumerate() {
+            let label = match i {
+                0 if measure_text_width(label) > label_width => truncate_str(label, label_width, "…").to_string(),
+                0 => label.clone(),
+                _ => String::new(),
+            };
+            // Bars grow from zero, negative values have none
+            let eighths = match s.values[r] {
+                Some(v) if max > 0.0 && v > 0.0 => (v / max * (bar_width * 8) as f64).round() as usize,
+                _ => 0,
+            };
+            let bar = format!("{}{}", "█".repeat(eighths / 8), EIGHTHS[eighths % 8]);
+            let mut line = format!("{:<label_width$} ", label, label_width = label_width);
+            if name_width > 0 {
+                line.push_str(&format!("{:<name_width$} ", s.name, name_width = name_width));
+            }
+            line.push_str(&format!("│{} {}", bar, value_texts[i][r]));
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+    }
+    output
+}
+
+fn render_sparkline(series: &[Series], width: usize) -> String {
+    let name_width = series.iter().map(|s| measure_text_width(&s.name)).max().unwrap_or(0);
+    let mut output = String::new();
+
+    for s in series {
+        let present: Vec<f64> = s.values.iter().flatten().copied().collect();
+        let min = present.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = present.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let summary = format!("min {}  max {}", format_number(min), format_number(max));
+        let available = width.saturating_sub(name_width + summary.len() + 4).max(10);
+
+        // Average values into buckets when there are more rows than columns
+        let buckets = s.values.len().min(available);
+        let spark: String = (0..buckets)
+            .map(|b| {
+                let start = b * s.values.len() / buckets;
+                let end = ((b + 1) * s.values.len() / buckets).max(start + 1);
+                let values: Vec<f64> = s.values[start..end].iter().flatten().copied().collect();
+                if values.is_empty() {
+                    return ' ';
+                }
+                let average = values.iter().sum::<f64>() / values.len() as f64;
+                let level = if max > min { ((average - min) / (max - min) * 7.0).round() as usize } else { 3 };
+                SPARKS[level.min(7)]
+            })
+            .collect();
+
+        output.push_str(&format!("{:<name_width$}  {}  {}\n", s.name, spark, summary, name_width = name_width));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::Column;
+    use serde_json::json;
+
+    fn column(name: &str, type_name: &str) -> Column {
+        Column { name: name.to_string(), type_name: type_name.to_string() }
+    }
+
+    fn events() -> ResultSet {
+        ResultSet {
+            columns: vec![column("minute", "timestamp"), column("events", "bigint"), column("errors", "integer null")],
+            rows: vec![
+                vec![json!("2024-02-07 01:00:00"), json!("10"), json!(1)],
+                vec![json!("2024-02-07 01:01:00"), json!("40"), json!(null)],
+                vec![json!("2024-02-07 01:03:00"), json!("20"), json!(3)],
+            ],
+            statistics: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(PlotSpec::parse(""), PlotSpec { kind: ChartKind::Line, columns: vec![] });
+        assert_eq!(
+            PlotSpec::parse("bar minute events"),
+            PlotSpec { kind: ChartKind::Bar, columns: vec!["minute".to_string(), "events".to_string()] }
+        );
+        assert_eq!(PlotSpec::parse(" Sparkline events ").kind, ChartKind::Sparkline);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("1970-01-02"), Some(86400.0));
+        assert_eq!(parse_time("2024-02-07 01:19:19.5+00"), Some(1707268759.5));
+        assert_eq!(parse_time("2024-02-07T03:19:19+02:00"), parse_time("2024-02-07 01:19:19"));
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let result = events();
+        assert_eq!(select_columns(&result, &PlotSpec::parse("")).unwrap(), (Some(0), vec![1, 2]));
+        assert_eq!(select_columns(&result, &PlotSpec::parse("errors")).unwrap(), (None, vec![2]));
+        assert_eq!(select_columns(&result, &PlotSpec::parse("minute events errors")).unwrap(), (Some(0), vec![1, 2]));
+
+        let error = select_columns(&result, &PlotSpec::parse("minute count")).unwrap_err().to_string();
+        assert_eq!(error, "Column 'count' not found in result; columns are: minute, events, errors");
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let chart = plot(&events(), &PlotSpec::parse("minute events"), 40).unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines.len(), LINE_HEIGHT + 3);
+        // The time axis is spaced by value: the peak is one third of the way, one minute out of three
+        assert_eq!(lines[0], "   40 ┤           **");
+        assert!(lines[LINE_HEIGHT / 2].starts_with("23.64 ┤"), "{}", chart);
+        assert!(lines[LINE_HEIGHT - 1].starts_with("   10 ┤*"), "{}", chart);
+        assert_eq!(lines[LINE_HEIGHT], format!("      └{}", "─".repeat(33)));
+        // Both ends of the axis do not fit in 40 columns
+        assert_eq!(lines[LINE_HEIGHT + 1], "       2024-02-07 01:00:00");
+        assert_eq!(lines[LINE_HEIGHT + 2], "       * events");
+        assert!(chart.lines().all(|line| measure_text_width(line) <= 40));
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        let chart = plot(&events(), &PlotSpec::parse("bar events"), 20).unwrap();
+        assert_eq!(chart, "1 │███▎ 10\n2 │█████████████ 40\n3 │██████▌ 20\n");
+
+        let chart = plot(&events(), &PlotSpec::parse("bar minute events errors"), 60).unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].starts_with("2024-02-07 01:00:00 events │"), "{}", chart);
+        assert_eq!(lines[3], format!("{:20}errors │", ""));
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let chart = plot(&events(), &PlotSpec::parse("spark minute events errors"), 80).unwrap();
+        assert_eq!(chart, "events  ▁█▃  min 10  max 40\nerrors  ▁ █  min 1  max 3\n");
+
+        // More rows than columns are averaged into buckets
+        let mut result = events();
+        result.rows = (0..100).map(|i| vec![json!(null), json!(i), json!(null)]).collect();
+        let chart = plot(&result, &PlotSpec::parse("spark events"), 40).unwrap();
+        assert_eq!(measure_text_width(chart.trim_end()), 40);
+    }
+
+    #[test]
+    fn test_errors() {
+        let result = events();
+        assert!(plot(&result, &PlotSpec::parse("minute"), 80).unwrap_err().to_string().contains("no numeric values"));
+
+        let empty = ResultSet { columns: result.columns.clone(), rows: vec![], statistics: None };
+        assert!(plot(&empty, &PlotSpec::parse(""), 80).is_err());
+
+        let text = ResultSet { columns: vec![column("name", "text")], rows: vec![vec![json!("a")]], statistics: None };
+        assert!(plot(&text, &PlotSpec::parse(""), 80).unwrap_err().to_string().contains("No numeric columns"));
+    }
+}
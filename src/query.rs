@@ -2,14 +2,23 @@ use once_cell::sync::Lazy;
 use pest::Parser;
 use pest_derive::Parser;
 use regex::Regex;
-use std::time::Instant;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 use tokio::{select, signal, task};
 use tokio_util::sync::CancellationToken;
 
 use crate::args::normalize_extras;
 use crate::auth::maybe_authenticate;
-use crate::context::Context;
-use crate::utils::spin;
+use crate::context::{Context, SettingSource};
+use crate::engine::{offer_start, restart_stopped_engine, stopped_engine};
+use crate::guard::check_statement;
+use crate::plot::{plot, terminal_width, PlotSpec};
+use crate::render::{local_format, render_display, Expanded, Format};
+use crate::stats::{write_stats, StatementStats};
+use crate::summarize::summarize;
+use crate::timing::{format_detailed, probe_connection, server_statistics, ConnectionTiming, Timing};
+use crate::result::{parse_response, Response};
+use crate::utils::{confirm, format_size, is_interactive, parse_size, spin};
 use crate::FIREBOLT_PROTOCOL_VERSION;
 use crate::USER_AGENT;
 
@@ -18,7 +27,7 @@ pub fn set_args(context: &mut Context, query: &str) -> Result<bool, Box<dyn std:
     // set flag = value;
     static SET_RE: Lazy<Regex> =
         Lazy::new(|| Regex::new(r#"(?i)^(?:--[^\n]*\n|/\*[\s\S]*\*/|[ \t\n])*set +([^ ]+?) *= *(.*?)\n*;?\n*$"#).unwrap());
-    let matches_set = SET_RE.captures(&query);
+    let matches_set = SET_RE.captures(query);
     if matches_set.is_none() {
         return Ok(false);
     }
@@ -36,6 +45,7 @@ pub fn set_args(context: &mut Context, query: &str) -> Result<bool, Box<dyn std:
         context.args.extra.append(&mut buf);
         buf.append(&mut context.args.extra);
         context.args.extra = normalize_extras(buf, false)?;
+        context.set_setting_source(key, SettingSource::SetStatement);
 
         if !context.args.concise && key == "engine" && value == "system" {
             eprintln!("\nTo query SYSTEM engine please run 'unset engine'\n");
@@ -44,17 +54,18 @@ pub fn set_args(context: &mut Context, query: &str) -> Result<bool, Box<dyn std:
 
     context.update_url();
 
-    return Ok(true);
+    Ok(true)
 }
 
 // Unset parameters via query
 pub fn unset_args(context: &mut Context, query: &str) -> Result<bool, Box<dyn std::error::Error>> {
     static UNSET_RE: Lazy<Regex> =
         Lazy::new(|| Regex::new(r#"(?i)^(?:--[^\n]*\n|/\*[\s\S]*\*/|[ \t\n])*unset +([^ ]+?)\s*(--.*)?\n*;?\n*$"#).unwrap());
-    if let Some(matches) = UNSET_RE.captures(&query) {
+    if let Some(matches) = UNSET_RE.captures(query) {
         let key = matches.get(1).unwrap().as_str();
         let prefix = format!("{key}=");
         context.args.extra.retain(|e| !e.starts_with(prefix.as_str()));
+        context.setting_sources.remove(key);
         if key == "format" {
             context.args.format = String::from("PSQL");
         } else if key == "database" {
@@ -69,8 +80,354 @@ pub fn unset_args(context: &mut Context, query: &str) -> Result<bool, Box<dyn st
     Ok(false)
 }
 
+// Substitute :name, :'name' and :"name" with client variables, outside of literals and comments.
+pub fn interpolate_variables(text: &str, variables: &BTreeMap<String, String>) -> String {
+    if variables.is_empty() || !text.contains(':') {
+        return text.to_string();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+
+        match c {
+            '\'' | '"' => {
+                // E-strings allow backslash escapes, other quotes are escaped by doubling
+                let escapes = c == '\'' && i > 0 && chars[i - 1].eq_ignore_ascii_case(&'e');
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += if escapes && chars[i] == '\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(chars.len());
+            }
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                let mut depth = 0;
+                while i < chars.len() {
+                    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                        depth += 1;
+                        i += 2;
+                    } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+                i = i.min(chars.len());
+            }
+            ':' if next == Some(':') => i += 2,
+            ':' if next == Some('\'') || next == Some('"') => {
+                let quote = next.unwrap_or_default();
+                let name_end = (i + 2..chars.len()).find(|&j| !is_name_char(chars[j])).unwrap_or(chars.len());
+                let name: String = chars[i + 2..name_end].iter().collect();
+                match variables.get(&name) {
+                    Some(value) if name_end > i + 2 && chars.get(name_end) == Some(&quote) => {
+                        let escaped = value.replace(quote, &format!("{quote}{quote}"));
+                        result.push_str(&format!("{quote}{escaped}{quote}"));
+                        i = name_end + 1;
+                        continue;
+                    }
+                    _ => i += 1,
+                }
+            }
+            ':' if next.is_some_and(is_name_char) => {
+                let name_end = (i + 1..chars.len()).find(|&j| !is_name_char(chars[j])).unwrap_or(chars.len());
+                let name: String = chars[i + 1..name_end].iter().collect();
+                match variables.get(&name) {
+                    Some(value) => {
+                        result.push_str(value);
+                        i = name_end;
+                        continue;
+                    }
+                    // Unknown variables are left untouched
+                    None => i = name_end,
+                }
+            }
+            _ => i += 1,
+        }
+
+        result.extend(&chars[start..i]);
+    }
+
+    result
+}
+
+// Run a query and return the result body instead of printing it. On failure the body is printed.
+pub async fn capture_query(context: &mut Context, query_text: String) -> Result<String, Box<dyn std::error::Error>> {
+    context.output_capture = Some(String::new());
+    let result = query(context, query_text).await;
+    let body = context.output_capture.take().unwrap_or_default();
+
+    if let Err(e) = result {
+        print!("{}", body);
+        return Err(e);
+    }
+
+    Ok(body)
+}
+
+// Run a query as TabSeparatedWithNames and return the body. Templates, charts and summaries are off meanwhile, so the
+// body can be parsed whatever the session options are.
+pub async fn capture_tsv(context: &mut Context, query_text: String) -> Result<String, Box<dyn std::error::Error>> {
+    let format = std::mem::replace(&mut context.args.format, String::from("TabSeparatedWithNames"));
+    let template = context.template.take();
+    let template_text = std::mem::take(&mut context.args.template);
+    let plot = std::mem::take(&mut context.args.plot);
+    let summarize = std::mem::take(&mut context.args.summarize);
+    context.update_url();
+    let result = capture_query(context, query_text).await;
+    context.args.format = format;
+    context.template = template;
+    context.args.template = template_text;
+    context.args.plot = plot;
+    context.args.summarize = summarize;
+    context.update_url();
+    result
+}
+
+// Run an internal lookup as TabSeparatedWithNames without time, spinner or statistics output, and return the body.
+pub async fn capture_internal(context: &mut Context, query_text: String) -> Result<String, Box<dyn std::error::Error>> {
+    let concise = std::mem::replace(&mut context.args.concise, true);
+    let stats_json = std::mem::take(&mut context.args.stats_json);
+    let result = capture_tsv(context, query_text).await;
+    context.args.concise = concise;
+    context.args.stats_json = stats_json;
+    result
+}
+
+// Print a JSONLines_Compact response in the session format and, with `keep`, keep the result. Returns false if the
+// query failed.
+fn output_response(context: &mut Context, format: Format, body: &str, keep: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    context.last_result = None;
+
+    match parse_response(body) {
+        Some(Response::Result(result)) => {
+            let expanded = Expanded::parse(&context.args.expanded).unwrap_or(Expanded::Off);
+            let text = if let Some(template) = &context.template {
+                template.render(&result)?
+            } else if !context.args.plot.is_empty() {
+                plot(&result, &PlotSpec::parse(&context.args.plot), terminal_width())?
+            } else if context.args.summarize {
+                let format = if format == Format::JsonLines { Format::Psql } else { format };
+                render_display(&summarize(&result, &[])?, format, expanded, &context.print_options)
+            } else if format == Format::JsonLines {
+                body.to_string()
+            } else {
+                render_display(&result, format, expanded, &context.print_options)
+            };
+            context.write_output(&text)?;
+            if keep {
+                context.last_result = Some(result);
+            }
+            Ok(true)
+        }
+        Some(Response::Errors(errors)) => {
+            if format == Format::JsonLines {
+                context.write_output(body)?;
+            } else {
+                for error in errors {
+                    context.write_output(&format!("ERROR: {}\n", error))?;
+                }
+            }
+            Ok(false)
+        }
+        // Not a JSONLines_Compact body, e.g. an older server or a plain-text error
+        None => {
+            context.write_output(body)?;
+            Ok(true)
+        }
+    }
+}
+
+// Rows in a JSONLines_Compact message line.
+fn data_rows(line: &[u8]) -> usize {
+    let Ok(message) = serde_json::from_slice::<serde_json::Value>(line) else {
+        return 0;
+    };
+    match message.get("message_type").and_then(|t| t.as_str()) {
+        Some("DATA") => message.get("data").and_then(|d| d.as_array()).map_or(0, |d| d.len()),
+        _ => 0,
+    }
+}
+
+// Keep the first `rows` rows of a DATA message line.
+fn truncate_data(line: &[u8], rows: usize) -> Vec<u8> {
+    let Ok(mut message) = serde_json::from_slice::<serde_json::Value>(line) else {
+        return vec![];
+    };
+    if let Some(data) = message.get_mut("data").and_then(|d| d.as_array_mut()) {
+        data.truncate(rows);
+    }
+    format!("{}\n", message).into_bytes()
+}
+
+// Response body read in chunks, cut at the --max-rows and --max-bytes limits.
+struct BodyReader {
+    lossless: bool,
+    header_lines: usize,
+    max_rows: usize,
+    max_bytes: usize,
+    body: Vec<u8>,
+    // End of the last complete line
+    scanned: usize,
+    lines: usize,
+    rows: usize,
+}
+
+// Format the server renders, which extra format= settings override.
+fn server_format(context: &Context) -> &str {
+    context.args.extra.iter().find_map(|e| e.strip_prefix("format=")).unwrap_or(&context.args.format)
+}
+
+// Header lines of server formats with one row per line (TSV and CSV), None for other formats.
+fn header_lines(format: &str) -> Option<usize> {
+    let format = format.to_lowercase();
+    if !["tabseparated", "tsv", "csv"].iter().any(|f| format.starts_with(f)) {
+        return None;
+    }
+    if format.ends_with("withnamesandtypes") {
+        Some(2)
+    } else if format.ends_with("withnames") {
+        Some(1)
+    } else {
+        Some(0)
+    }
+}
+
+impl BodyReader {
+    fn new(context: &Context, lossless: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        // Rows of server-rendered formats are lines after the header
+        let header_lines = header_lines(server_format(context)).unwrap_or(0);
+
+        Ok(Self {
+            lossless,
+            header_lines,
+            max_rows: context.args.max_rows,
+            max_bytes: parse_size(&context.args.max_bytes)?,
+            body: vec![],
+            scanned: 0,
+            lines: 0,
+            rows: 0,
+        })
+    }
+
+    // Add a chunk. Once a limit is reached returns a notice, and the body holds the complete rows that fit.
+    fn push(&mut self, chunk: &[u8]) -> Option<String> {
+        self.body.extend_from_slice(chunk);
+
+        while let Some(pos) = self.body[self.scanned..].iter().position(|&b| b == b'\n') {
+            let end = self.scanned + pos + 1;
+            let line_rows = if self.lossless {
+                data_rows(&self.body[self.scanned..end])
+            } else {
+                usize::from(self.lines >= self.header_lines)
+            };
+            self.lines += 1;
+
+            if self.max_rows > 0 && self.rows + line_rows > self.max_rows {
+                let line = self.body[self.scanned..end].to_vec();
+                self.body.truncate(self.scanned);
+                if self.lossless && self.max_rows > self.rows {
+                    self.body.extend(truncate_data(&line, self.max_rows - self.rows));
+                }
+                return Some(format!("Result truncated to {} rows (--max-rows), the query was cancelled", self.max_rows));
+            }
+            self.rows += line_rows;
+            self.scanned = end;
+        }
+
+        if self.max_bytes > 0 && self.body.len() > self.max_bytes {
+            let end = self.body[..self.max_bytes].iter().rposition(|&b| b == b'\n').map_or(0, |p| p + 1);
+            self.body.truncate(end);
+            return Some(format!("Result truncated to {} (--max-bytes), the query was cancelled", format_size(self.max_bytes)));
+        }
+
+        None
+    }
+
+    // Drop a trailing incomplete line.
+    fn cut(&mut self) {
+        self.body.truncate(self.scanned);
+    }
+
+    fn text(self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+// Read the response body, stopping at the size limits or when the user declines to read a large response.
+// Dropping the response closes the request, which cancels the query. Returns the body, whether a lossless result is
+// small enough to keep for \last and \export, and a notice if the body was cut.
+async fn read_body(
+    context: &Context,
+    mut resp: reqwest::Response,
+    lossless: bool,
+) -> Result<(String, bool, Option<String>), Box<dyn std::error::Error>> {
+    let mut reader = BodyReader::new(context, lossless)?;
+    let limit = parse_size(&context.args.max_result_memory)?;
+    let mut keep = lossless;
+    let confirm_bytes = if is_interactive() { parse_size(&context.args.confirm_bytes)? } else { 0 };
+    // No need to ask when --max-bytes stops reading first
+    let mut confirmed = confirm_bytes == 0 || (reader.max_bytes > 0 && reader.max_bytes <= confirm_bytes);
+    let declined = || Some(String::from("Stopped reading the response, the query was cancelled"));
+
+    if let Some(length) = resp.content_length() {
+        if !confirmed && length as usize > confirm_bytes {
+            if !confirm(&format!("The response is {}. Read it? [y/N] ", format_size(length as usize)))? {
+                return Ok((String::new(), false, declined()));
+            }
+            confirmed = true;
+        }
+    }
+
+    while let Some(chunk) = resp.chunk().await? {
+        let notice = reader.push(&chunk);
+        // Once the body grows past --max-result-memory the result is not kept
+        if keep && reader.body.len() > limit {
+            keep = false;
+            if context.args.verbose {
+                eprintln!("Result exceeds --max-result-memory={}, not kept", context.args.max_result_memory);
+            }
+        }
+        if notice.is_some() {
+            return Ok((reader.text(), keep, notice));
+        }
+        if !confirmed && reader.body.len() > confirm_bytes {
+            if !confirm(&format!("The response is larger than {}. Continue reading? [y/N] ", format_size(confirm_bytes)))? {
+                reader.cut();
+                return Ok((reader.text(), keep, declined()));
+            }
+            confirmed = true;
+        }
+    }
+
+    Ok((reader.text(), keep, None))
+}
+
 // Send query and print result.
 pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<dyn std::error::Error>> {
+    run_query(context, query_text, true).await
+}
+
+// Send query and print result. If it fails on a stopped engine that is then started, the original text runs once
+// more with `retry` false: it is not retried again and the statement guard does not ask a second time.
+async fn run_query(context: &mut Context, original: String, retry: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let query_text = interpolate_variables(&original, &context.variables);
+
     // Handle set/unset commands
     if set_args(context, &query_text)? {
         if !context.args.concise && !context.args.hide_pii {
@@ -88,15 +445,79 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
         return Ok(());
     }
 
+    if retry {
+        if let Err(e) = check_statement(context, &query_text) {
+            eprintln!("{}", e);
+            return Err("Query failed".into());
+        }
+    }
+
     maybe_authenticate(context).await?;
 
+    // Results in locally rendered formats are requested losslessly (see get_url), so they can be kept for \last
+    let local_format = local_format(&context.args);
+    // Rows of other server formats span several lines, so they cannot be counted
+    if context.args.max_rows > 0 && local_format.is_none() && header_lines(server_format(context)).is_none() {
+        return Err(format!("--max-rows does not support format {}; use PSQL, Vertical, TSV or CSV", server_format(context)).into());
+    }
+
     if context.args.verbose {
         eprintln!("URL: {}", context.url);
         eprintln!("QUERY: {}", query_text);
     }
 
+    let timing = Timing::parse(&context.args.timing).unwrap_or(Timing::On);
+    let connection = if timing == Timing::Detailed && !context.args.concise {
+        // Ctrl-C during the probe cancels the query before it is sent
+        select! {
+            _ = signal::ctrl_c() => {
+                eprintln!("^C");
+                return Err("Query failed".into());
+            }
+            connection = probe_connection(&context.url) => Some(connection.map_err(|e| e.to_string())),
+        }
+    } else {
+        None
+    };
+
+    let mut stats = StatementStats::start(&query_text);
     let start = Instant::now();
+    let result = send_query(context, query_text, local_format, timing, connection, &mut stats, retry).await;
+
+    // Statements that fail with an error are recorded too
+    if !context.args.stats_json.is_empty() {
+        if let (Err(error), None) = (&result, &stats.error) {
+            stats.error = Some(error.to_string());
+        }
+        stats.set_duration(start.elapsed());
+        write_stats(context, stats)?;
+    }
+
+    let (query_failed, start_engine) = result?;
+    if let Some(engine) = start_engine {
+        Box::pin(restart_stopped_engine(context, &engine)).await?;
+        return Box::pin(run_query(context, original, false)).await;
+    }
+
+    if query_failed {
+        Err("Query failed".into())
+    } else {
+        Ok(())
+    }
+}
 
+// Send a statement and print its response. Returns whether the query failed, and with `retry` the stopped engine
+// to start before running it again.
+async fn send_query(
+    context: &mut Context,
+    query_text: String,
+    local_format: Option<Format>,
+    timing: Timing,
+    connection: Option<Result<ConnectionTiming, String>>,
+    stats: &mut StatementStats,
+    retry: bool,
+) -> Result<(bool, Option<String>), Box<dyn std::error::Error>> {
+    let start = Instant::now();
     let mut request = reqwest::Client::builder()
         .http2_keep_alive_timeout(std::time::Duration::from_secs(3600))
         .http2_keep_alive_interval(Some(std::time::Duration::from_secs(60)))
@@ -127,6 +548,7 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
     };
 
     let mut query_failed = false;
+    let mut start_engine = None;
 
     select! {
         _ = signal::ctrl_c() => {
@@ -137,6 +559,7 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
             if !context.args.concise {
                 eprintln!("^C");
             }
+            stats.error = Some(String::from("Cancelled"));
             query_failed = true;
         }
         response = async_resp => {
@@ -147,6 +570,9 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
             }
 
             let mut maybe_request_id: Option<String> = None;
+            let mut transfer = Duration::ZERO;
+            let mut body_size = 0;
+            let mut statistics = None;
             match response {
                 Ok(resp) => {
                     let mut updated_url = false;
@@ -156,9 +582,11 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
                             updated_url = true;
                         } else if header == "firebolt-update-parameters" {
                             set_args(context, format!("set {}", value.to_str()?).as_str())?;
+                            context.set_setting_source(value.to_str()?, SettingSource::ServerHeader);
                             updated_url = true;
                         } else if header == "X-REQUEST-ID" {
                             maybe_request_id = value.to_str().map_or(None, |l| Some(String::from(l)));
+                            stats.request_id = maybe_request_id.clone();
                             updated_url = true;
                         } else if header == "firebolt-update-endpoint" {
                             let header_str = value.to_str()?;
@@ -192,6 +620,7 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
                                 // Process other query parameters
                                 for param in other_params {
                                     set_args(context, format!("set {};", param).as_str())?;
+                                    context.set_setting_source(param, SettingSource::ServerHeader);
                                 }
                             } else {
                                 // No query parameters, just set the URL
@@ -205,46 +634,85 @@ pub async fn query(context: &mut Context, query_text: String) -> Result<(), Box<
                     }
 
                     let status = resp.status();
-                    let body = resp.text().await?;
+                    let transfer_start = Instant::now();
+                    let (body, keep, notice) = read_body(context, resp, local_format.is_some()).await?;
+                    transfer = transfer_start.elapsed();
+                    body_size = body.len();
+                    stats.set_response(status.as_u16(), &body);
+                    if connection.is_some() {
+                        statistics = server_statistics(&body);
+                    }
+
+                    // A statement on a stopped engine is run again once the engine is started
+                    let mut stopped = if status.is_success() { None } else { stopped_engine(context, &body) };
+                    if let Some(engine) = &stopped {
+                        if retry && offer_start(context, engine, "run the statement")? {
+                            start_engine = stopped.take();
+                        }
+                    }
+
+                    match local_format {
+                        _ if start_engine.is_some() => {}
+                        Some(format) => {
+                            if !output_response(context, format, &body, keep)? {
+                                query_failed = true;
+                            }
+                        }
+                        None => {
+                            context.last_result = None;
+                            context.write_output(&body)?;
+                        }
+                    }
 
-                    // on stdout, on purpose
-                    print!("{}", body);
+                    if let Some(notice) = notice {
+                        eprintln!("{}", notice);
+                    }
+                    if let Some(engine) = stopped {
+                        eprintln!(
+                            "Engine '{}' is not running; start it with 'fb engine start {}' or use --auto-start-engine.",
+                            engine, engine
+                        );
+                    }
 
                     if !status.is_success() {
                         query_failed = true;
                     }
                 }
                 Err(error) => {
+                    stats.error = Some(error.to_string());
                     if context.args.verbose {
                         eprintln!("Failed to send the request: {:?}", error);
                     } else {
-                        eprintln!("Failed to send the request: {}", error.to_string());
+                        eprintln!("Failed to send the request: {}", error);
                     }
                     query_failed = true;
                 },
             };
 
             if !context.args.concise {
-                let elapsed = format!("{:?}", elapsed / 100000 * 100000);
-                eprintln!("Time: {elapsed}");
+                match &connection {
+                    Some(connection) => eprint!("{}", format_detailed(connection, elapsed, transfer, body_size, statistics.as_deref())),
+                    None if timing == Timing::Off => {}
+                    None => {
+                        // First byte plus transfer, as in the detailed report
+                        let time = format!("{:?}", (elapsed + transfer) / 100000 * 100000);
+                        eprintln!("Time: {time}");
+                    }
+                }
                 if let Some(request_id) = maybe_request_id {
                     eprintln!("Request Id: {request_id}");
                 }
-                eprintln!("")
+                eprintln!()
             }
         }
     };
 
-    if query_failed {
-        Err("Query failed".into())
-    } else {
-        Ok(())
-    }
+    Ok((query_failed, start_engine))
 }
 
 #[derive(Parser)]
 #[grammar = "sql.pest"]
-struct SQLParser;
+pub(crate) struct SQLParser;
 
 pub fn try_split_queries(s: &str) -> Option<Vec<String>> {
     match SQLParser::parse(Rule::queries, s) {
@@ -288,6 +756,90 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_capture_tsv_restores_session_options() {
+        let mut args = get_args().unwrap();
+        args.host = "localhost:59999".to_string();
+        args.concise = true;
+        args.format = "PSQL".to_string();
+        args.template = "{id}".to_string();
+        args.plot = "bar day total".to_string();
+        args.summarize = true;
+
+        let mut context = Context::new(args);
+        assert!(capture_tsv(&mut context, "select 42".to_string()).await.is_err());
+        assert_eq!(context.args.format, "PSQL");
+        assert_eq!(context.args.template, "{id}");
+        assert!(context.template.is_some());
+        assert_eq!(context.args.plot, "bar day total");
+        assert!(context.args.summarize);
+    }
+
+    #[test]
+    fn test_body_reader_max_rows() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.max_rows = 3;
+        context.args.max_bytes = String::from("0");
+
+        // Lossless results are cut inside a DATA message
+        let mut reader = BodyReader::new(&context, true).unwrap();
+        assert_eq!(reader.push(b"{\"message_type\":\"START\",\"result_columns\":[]}\n{\"message_type\":\"DATA\",\"da"), None);
+        let notice = reader.push(b"ta\":[[1],[2]]}\n{\"message_type\":\"DATA\",\"data\":[[3],[4]]}\n").unwrap();
+        assert!(notice.contains("3 rows (--max-rows)"), "{}", notice);
+        let Some(Response::Result(result)) = parse_response(&reader.text()) else {
+            panic!("expected a result set");
+        };
+        assert_eq!(result.rows.len(), 3);
+
+        // Server-rendered rows are lines after the header
+        context.args.format = String::from("TabSeparatedWithNames");
+        let mut reader = BodyReader::new(&context, false).unwrap();
+        assert!(reader.push(b"id\n1\n2\n3\n4\n").is_some());
+        assert_eq!(reader.text(), "id\n1\n2\n3\n");
+
+        assert_eq!(header_lines("CSVWithNamesAndTypes"), Some(2));
+        assert_eq!(header_lines("TabSeparated"), Some(0));
+        assert_eq!(header_lines("JSON"), None);
+    }
+
+    #[test]
+    fn test_body_reader_max_bytes() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.max_rows = 0;
+        context.args.max_bytes = String::from("10");
+
+        let mut reader = BodyReader::new(&context, false).unwrap();
+        assert_eq!(reader.push(b"1234\n"), None);
+        let notice = reader.push(b"5678\n9012\n").unwrap();
+        assert!(notice.contains("10B (--max-bytes)"), "{}", notice);
+        assert_eq!(reader.text(), "1234\n5678\n");
+
+        let mut reader = BodyReader::new(&context, false).unwrap();
+        reader.push(b"1234\n56");
+        reader.cut();
+        assert_eq!(reader.text(), "1234\n");
+    }
+
+    #[test]
+    fn test_interpolate_variables() {
+        let mut variables = BTreeMap::new();
+        variables.insert("max_id".to_string(), "42".to_string());
+        variables.insert("name".to_string(), "it's".to_string());
+
+        assert_eq!(interpolate_variables("SELECT * FROM t WHERE id > :max_id;", &variables), "SELECT * FROM t WHERE id > 42;");
+        assert_eq!(interpolate_variables("SELECT :'name';", &variables), "SELECT 'it''s';");
+        assert_eq!(interpolate_variables("SELECT 1 AS :\"max_id\";", &variables), "SELECT 1 AS \"42\";");
+
+        // Casts, literals, comments and unknown variables are left alone
+        assert_eq!(interpolate_variables("SELECT '1'::int, :unknown;", &variables), "SELECT '1'::int, :unknown;");
+        assert_eq!(interpolate_variables("SELECT ':max_id', E'\\' :max_id';", &variables), "SELECT ':max_id', E'\\' :max_id';");
+        assert_eq!(
+            interpolate_variables("SELECT 1 -- :max_id\n/* :max_id /* :max_id */ */ + :max_id;", &variables),
+            "SELECT 1 -- :max_id\n/* :max_id /* :max_id */ */ + 42;"
+        );
+        assert_eq!(interpolate_variables("SELECT :max_id", &BTreeMap::new()), "SELECT :max_id");
+    }
+
     #[test]
     fn test_basic_queries() {
         // Simple queries
@@ -0,0 +1,998 @@
+use console::{measure_text_width, Term};
+use serde_json::{json, Map, Value};
+
+use crate::args::Args;
+use crate::result::ResultSet;
+use crate::utils::parse_size;
+
+// Formats fb renders on the client from a retained result set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Psql,
+    Vertical,
+    TabSeparated,
+    CsvWithNames,
+    Json,
+    JsonLines,
+    Unaligned,
+    Markdown,
+    Html,
+    Csv,
+    AsciiDoc,
+    Latex,
+}
+
+// Names accepted by \last and \export.
+pub const FORMAT_NAMES: &str = "PSQL, Vertical, unaligned, markdown, html, csv, asciidoc, latex, TSV (TabSeparatedWithNames), \
+                                CSVWithNames, JSON, JSONLines_Compact";
+
+impl Format {
+    // Parse a format name, case-insensitively. Server format names map to their local equivalent.
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "psql" => Some(Format::Psql),
+            "vertical" => Some(Format::Vertical),
+            "tsv" | "tabseparatedwithnames" => Some(Format::TabSeparated),
+            "csvwithnames" => Some(Format::CsvWithNames),
+            "json" => Some(Format::Json),
+            "jsonl" | "jsonlines_compact" => Some(Format::JsonLines),
+            name => Format::client_only(name),
+        }
+    }
+
+    // Session formats that can be rendered locally.
+    pub fn for_session(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "psql" => Some(Format::Psql),
+            "vertical" => Some(Format::Vertical),
+            "jsonlines_compact" => Some(Format::JsonLines),
+            name => Format::client_only(name),
+        }
+    }
+
+    // Formats the server does not know; they are never sent as output_format.
+    pub fn client_only(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "unaligned" => Some(Format::Unaligned),
+            "markdown" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            "csv" => Some(Format::Csv),
+            "asciidoc" => Some(Format::AsciiDoc),
+            "latex" => Some(Format::Latex),
+            _ => None,
+        }
+    }
+}
+
+// Session format fb renders itself from a lossless result, or None when the server's output is printed as is.
+// PSQL and Vertical are rendered locally so their results can be kept, unless --max-result-memory is 0 and there is
+// no --max-rows; JSONLines_Compact is lossless already, and the server does not know the client-only formats.
+pub fn local_format(args: &Args) -> Option<Format> {
+    if args.extra.iter().any(|e| e.starts_with("format=")) {
+        return None;
+    }
+    // \pset options only apply to results fb renders itself
+    let print_options = PrintOptions::from_settings(&args.pset).unwrap_or_default() != PrintOptions::default();
+    let session_format = Format::for_session(&args.format);
+    // Templates, charts and summaries need column names and raw values whatever the session format is
+    if !args.template.is_empty() || !args.plot.is_empty() || args.summarize {
+        return Some(session_format.unwrap_or(Format::Psql));
+    }
+    match session_format? {
+        Format::JsonLines => Some(Format::JsonLines),
+        format if Format::client_only(&args.format).is_some() => Some(format),
+        // Rows of PSQL and Vertical output span several lines, so --max-rows counts them in the lossless result
+        format if parse_size(&args.max_result_memory).is_ok_and(|limit| limit > 0) || print_options || args.max_rows > 0 => Some(format),
+        // Expanded display lays PSQL results out in blocks whatever the server is asked for
+        Format::Psql if Expanded::parse(&args.expanded).is_some_and(|e| e != Expanded::Off) => Some(Format::Psql),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineStyle {
+    Ascii,
+    Unicode,
+}
+
+// Client-side output options set with \pset, --pset or the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintOptions {
+    pub null: String,
+    pub border: u8,
+    pub linestyle: LineStyle,
+    pub footer: bool,
+    pub numericlocale: bool,
+    pub tuples_only: bool,
+    pub fieldsep: String,
+    pub recordsep: String,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            null: String::new(),
+            border: 1,
+            linestyle: LineStyle::Ascii,
+            footer: false,
+            numericlocale: false,
+            tuples_only: false,
+            fieldsep: String::from("|"),
+            recordsep: String::from("\n"),
+        }
+    }
+}
+
+// Names of \pset options, in display order.
+pub const PRINT_OPTION_NAMES: &[&str] =
+    &["null", "border", "linestyle", "footer", "numericlocale", "tuples_only", "fieldsep", "recordsep"];
+
+// Boolean options accept on/off and friends; no value toggles.
+fn parse_toggle(name: &str, value: Option<&str>, current: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    match value.map(|v| v.to_lowercase()) {
+        None => Ok(!current),
+        Some(v) if ["on", "true", "yes", "1"].contains(&v.as_str()) => Ok(true),
+        Some(v) if ["off", "false", "no", "0"].contains(&v.as_str()) => Ok(false),
+        Some(v) => Err(format!("Invalid value '{}' for {}: expected on or off", v, name).into()),
+    }
+}
+
+// Separators may be given with \t and \n escapes.
+fn unescape_separator(value: &str) -> String {
+    value.replace("\\t", "\t").replace("\\n", "\n")
+}
+
+fn escape_separator(value: &str) -> String {
+    value.replace('\t', "\\t").replace('\n', "\\n")
+}
+
+impl PrintOptions {
+    // Build options from "name=value" settings, as given with --pset or in the config file.
+    pub fn from_settings(settings: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = Self::default();
+        for setting in settings {
+            match setting.split_once('=') {
+                Some((name, value)) => options.set(name.trim(), Some(value))?,
+                None => options.set(setting.trim(), None)?,
+            }
+        }
+        Ok(options)
+    }
+
+    // Change an option. Without a value booleans are toggled and other options are reset.
+    pub fn set(&mut self, name: &str, value: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let defaults = Self::default();
+        match name {
+            "null" => self.null = value.map_or(defaults.null, String::from),
+            "border" => {
+                self.border = match value {
+                    None => defaults.border,
+                    Some(v) => {
+                        let border = v.trim().parse().ok().filter(|b| *b <= 2);
+                        border.ok_or_else(|| format!("Invalid border '{}': expected 0, 1 or 2", v))?
+                    }
+                }
+            }
+            "linestyle" => {
+                self.linestyle = match value.map(|v| v.to_lowercase()).as_deref() {
+                    None => defaults.linestyle,
+                    Some("ascii") => LineStyle::Ascii,
+                    Some("unicode") => LineStyle::Unicode,
+                    Some(v) => return Err(format!("Invalid linestyle '{}': expected ascii or unicode", v).into()),
+                }
+            }
+            "footer" => self.footer = parse_toggle(name, value, self.footer)?,
+            "numericlocale" => self.numericlocale = parse_toggle(name, value, self.numericlocale)?,
+            "tuples_only" => self.tuples_only = parse_toggle(name, value, self.tuples_only)?,
+            "fieldsep" => self.fieldsep = value.map_or(defaults.fieldsep, unescape_separator),
+            "recordsep" => self.recordsep = value.map_or(defaults.recordsep, unescape_separator),
+            _ => return Err(format!("Unknown output option '{}'. Available: {}", name, PRINT_OPTION_NAMES.join(", ")).into()),
+        }
+        Ok(())
+    }
+
+    // Current value of an option, as it would be set.
+    pub fn get(&self, name: &str) -> Option<String> {
+        let on_off = |b: bool| String::from(if b { "on" } else { "off" });
+        Some(match name {
+            "null" => self.null.clone(),
+            "border" => self.border.to_string(),
+            "linestyle" => String::from(if self.linestyle == LineStyle::Unicode { "unicode" } else { "ascii" }),
+            "footer" => on_off(self.footer),
+            "numericlocale" => on_off(self.numericlocale),
+            "tuples_only" => on_off(self.tuples_only),
+            "fieldsep" => escape_separator(&self.fieldsep),
+            "recordsep" => escape_separator(&self.recordsep),
+            _ => return None,
+        })
+    }
+}
+
+// Expanded display mode of \x.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Expanded {
+    Off,
+    On,
+    Auto,
+}
+
+impl Expanded {
+    pub fn parse(value: &str) -> Option<Expanded> {
+        match value.to_lowercase().as_str() {
+            "off" | "false" | "0" => Some(Expanded::Off),
+            "on" | "true" | "1" => Some(Expanded::On),
+            "auto" => Some(Expanded::Auto),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Expanded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expanded::Off => write!(f, "off"),
+            Expanded::On => write!(f, "on"),
+            Expanded::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+// Render for display: PSQL tables switch to vertical layout when expanded, or in auto mode when wider than the terminal.
+pub fn render_display(result: &ResultSet, format: Format, expanded: Expanded, options: &PrintOptions) -> String {
+    if format != Format::Psql {
+        return render(result, format, options);
+    }
+
+    match expanded {
+        Expanded::Off => render_psql(result, options),
+        Expanded::On => render_vertical(result, options),
+        Expanded::Auto => {
+            let table = render_psql(result, options);
+            let width = table.lines().map(measure_text_width).max().unwrap_or(0);
+            match Term::stdout().size_checked() {
+                Some((_, columns)) if width > columns as usize => render_vertical(result, options),
+                _ => table,
+            }
+        }
+    }
+}
+
+// Render a result set in the given format. Print options apply to the human-readable formats only.
+pub fn render(result: &ResultSet, format: Format, options: &PrintOptions) -> String {
+    match format {
+        Format::Psql => render_psql(result, options),
+        Format::Vertical => render_vertical(result, options),
+        Format::Unaligned => render_unaligned(result, options),
+        Format::TabSeparated => render_tsv(result),
+        Format::CsvWithNames => render_csv_with_names(result),
+        Format::Markdown => render_markdown(result, options),
+        Format::Html => render_html(result, options),
+        Format::Csv => render_csv(result),
+        Format::AsciiDoc => render_asciidoc(result, options),
+        Format::Latex => render_latex(result, options),
+        Format::Json => render_json(result),
+        Format::JsonLines => render_jsonlines(result),
+    }
+}
+
+// Text of a value as shown in tables. Arrays use the PostgreSQL {a,b} notation.
+pub(crate) fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => String::from(if *b { "t" } else { "f" }),
+        Value::Number(n) => n.to_string(),
+        Value::Array(values) => {
+            let elements: Vec<String> = values.iter().map(array_element_text).collect();
+            format!("{{{}}}", elements.join(","))
+        }
+        Value::Object(_) => value.to_string(),
+    }
+}
+
+fn array_element_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("NULL"),
+        Value::String(s) if s.is_empty() || s.contains(|c: char| c == ',' || c == '{' || c == '}' || c == '"' || c.is_whitespace()) => {
+            format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        value => value_text(value),
+    }
+}
+
+// Numbers are right-aligned in tables.
+pub(crate) fn is_numeric_type(type_name: &str) -> bool {
+    let base = type_name.trim_end_matches(" null").to_lowercase();
+    let base = base.split('(').next().unwrap_or_default().trim();
+    matches!(
+        base,
+        "int" | "integer" | "bigint" | "long" | "real" | "float" | "double" | "double precision" | "numeric" | "decimal"
+    )
+}
+
+fn pad(text: &str, width: usize, right_align: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(measure_text_width(text)));
+    if right_align {
+        format!("{}{}", fill, text)
+    } else {
+        format!("{}{}", text, fill)
+    }
+}
+
+fn center(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(measure_text_width(text));
+    format!("{}{}{}", " ".repeat(fill / 2), text, " ".repeat(fill - fill / 2))
+}
+
+// Insert thousands separators into the integer part of a number.
+fn group_thousands(text: &str) -> String {
+    let (sign, rest) = text.strip_prefix('-').map_or(("", text), |rest| ("-", rest));
+    let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let (digits, tail) = rest.split_at(digits_end);
+    if digits.is_empty() || !(tail.is_empty() || tail.starts_with('.') || tail.starts_with(['e', 'E'])) {
+        return text.to_string();
+    }
+
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}{}{}", sign, grouped, tail)
+}
+
+// Text of a cell in the human-readable formats.
+fn display_text(value: &Value, numeric: bool, options: &PrintOptions) -> String {
+    match value {
+        Value::Null => options.null.clone(),
+        value if numeric && options.numericlocale => group_thousands(&value_text(value)),
+        value => value_text(value),
+    }
+}
+
+fn row_count_footer(rows: usize) -> String {
+    format!("({} {})\n", rows, if rows == 1 { "row" } else { "rows" })
+}
+
+// Characters used to draw tables: horizontal, vertical, and left/middle/right joints of the top, middle and bottom rules.
+struct LineChars {
+    horizontal: &'static str,
+    vertical: &'static str,
+    top: [&'static str; 3],
+    middle: [&'static str; 3],
+    bottom: [&'static str; 3],
+}
+
+const ASCII_LINES: LineChars =
+    LineChars { horizontal: "-", vertical: "|", top: ["+", "+", "+"], middle: ["+", "+", "+"], bottom: ["+", "+", "+"] };
+
+const UNICODE_LINES: LineChars = LineChars {
+    horizontal: "─",
+    vertical: "│",
+    top: ["┌", "┬", "┐"],
+    middle: ["├", "┼", "┤"],
+    bottom: ["└", "┴", "┘"],
+};
+
+// Horizontal rule of a table with the given border style.
+fn table_rule(widths: &[usize], lines: &LineChars, joints: [&str; 3], border: u8) -> String {
+    match border {
+        0 => widths.iter().map(|w| lines.horizontal.repeat(*w)).collect::<Vec<_>>().join(" "),
+        // Like the server's output, the rule ends where the last column ends
+        1 => {
+            let segments: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, w)| lines.horizontal.repeat(if i + 1 == widths.len() { w + 1 } else { w + 2 }))
+                .collect();
+            segments.join(joints[1])
+        }
+        _ => {
+            let segments: Vec<String> = widths.iter().map(|w| lines.horizontal.repeat(w + 2)).collect();
+            format!("{}{}{}", joints[0], segments.join(joints[1]), joints[2])
+        }
+    }
+}
+
+// One line of a table from already padded cells.
+fn table_line(cells: &[String], lines: &LineChars, border: u8) -> String {
+    match border {
+        0 => cells.join(" ").trim_end().to_string(),
+        1 => format!(" {}", cells.join(&format!(" {} ", lines.vertical))).trim_end().to_string(),
+        _ => format!("{} {} {}", lines.vertical, cells.join(&format!(" {} ", lines.vertical)), lines.vertical),
+    }
+}
+
+// psql-style aligned table.
+fn render_psql(result: &ResultSet, options: &PrintOptions) -> String {
+    if result.columns.is_empty() {
+        return String::new();
+    }
+
+    let lines = if options.linestyle == LineStyle::Unicode { &UNICODE_LINES } else { &ASCII_LINES };
+    let numeric: Vec<bool> = result.columns.iter().map(|c| is_numeric_type(&c.type_name)).collect();
+    let cells: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().enumerate().map(|(i, value)| display_text(value, numeric[i], options)).collect())
+        .collect();
+    let widths: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let header = if options.tuples_only { 0 } else { measure_text_width(&column.name) };
+            cells
+                .iter()
+                .flat_map(|row| row.get(i).map(|cell| cell.lines().map(measure_text_width).max().unwrap_or(0)))
+                .chain(std::iter::once(header))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut output = String::new();
+    if options.border == 2 {
+        output += &table_rule(&widths, lines, lines.top, options.border);
+        output += "\n";
+    }
+    if !options.tuples_only {
+        let header: Vec<String> = result.columns.iter().zip(&widths).map(|(c, w)| center(&c.name, *w)).collect();
+        output += &table_line(&header, lines, options.border);
+        output += "\n";
+        output += &table_rule(&widths, lines, lines.middle, options.border);
+        output += "\n";
+    }
+
+    for row in &cells {
+        // Multi-line values span several output lines
+        let height = row.iter().map(|cell| cell.lines().count().max(1)).max().unwrap_or(1);
+        for line_index in 0..height {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| pad(cell.lines().nth(line_index).unwrap_or_default(), widths[i], numeric[i]))
+                .collect();
+            output += &table_line(&line, lines, options.border);
+            output += "\n";
+        }
+    }
+
+    if options.border == 2 {
+        output += &table_rule(&widths, lines, lines.bottom, options.border);
+        output += "\n";
+    }
+    if options.footer && !options.tuples_only {
+        output += &row_count_footer(result.rows.len());
+    }
+
+    output
+}
+
+// One "name: value" block per row.
+fn render_vertical(result: &ResultSet, options: &PrintOptions) -> String {
+    let width = result.columns.iter().map(|c| measure_text_width(&c.name)).max().unwrap_or(0) + 1;
+    let mut blocks: Vec<String> = vec![];
+
+    for (index, row) in result.rows.iter().enumerate() {
+        let mut block = String::new();
+        if !options.tuples_only {
+            let title = format!("Row {}:", index + 1);
+            block += &format!("{}\n{}\n", title, "─".repeat(measure_text_width(&title)));
+        }
+        for (column, value) in result.columns.iter().zip(row) {
+            let text = display_text(value, is_numeric_type(&column.type_name), options);
+            block += &format!("{} {}\n", pad(&format!("{}:", column.name), width, false), text);
+        }
+        blocks.push(block);
+    }
+
+    blocks.join("\n")
+}
+
+// Values separated by fieldsep, rows by recordsep.
+fn render_unaligned(result: &ResultSet, options: &PrintOptions) -> String {
+    let mut records: Vec<String> = vec![];
+    if !options.tuples_only {
+        records.push(result.columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(&options.fieldsep));
+    }
+    for row in &result.rows {
+        let values: Vec<String> = row
+            .iter()
+            .zip(&result.columns)
+            .map(|(value, column)| display_text(value, is_numeric_type(&column.type_name), options))
+            .collect();
+        records.push(values.join(&options.fieldsep));
+    }
+    if options.footer && !options.tuples_only {
+        records.push(row_count_footer(result.rows.len()).trim_end().to_string());
+    }
+
+    let mut output = records.join(&options.recordsep);
+    if !output.is_empty() {
+        output += "\n";
+    }
+    output
+}
+
+fn tsv_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+// TabSeparatedWithNames; NULL is \N.
+fn render_tsv(result: &ResultSet) -> String {
+    let mut output = String::new();
+    let header: Vec<String> = result.columns.iter().map(|c| tsv_escape(&c.name)).collect();
+    output += &header.join("\t");
+    output += "\n";
+
+    for row in &result.rows {
+        let values: Vec<String> = row
+            .iter()
+            .map(|value| if value.is_null() { String::from("\\N") } else { tsv_escape(&value_text(value)) })
+            .collect();
+        output += &values.join("\t");
+        output += "\n";
+    }
+
+    output
+}
+
+fn csv_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+// CSVWithNames; text is quoted, numbers and booleans are not, NULL is empty.
+fn render_csv_with_names(result: &ResultSet) -> String {
+    let mut output = String::new();
+    let header: Vec<String> = result.columns.iter().map(|c| csv_quote(&c.name)).collect();
+    output += &header.join(",");
+    output += "\n";
+
+    for row in &result.rows {
+        let values: Vec<String> = row
+            .iter()
+            .map(|value| match value {
+                Value::Null => String::new(),
+                Value::Number(_) | Value::Bool(_) => value_text(value),
+                value => csv_quote(&value_text(value)),
+            })
+            .collect();
+        output += &values.join(",");
+        output += "\n";
+    }
+
+    output
+}
+
+// RFC 4180 CSV: CRLF line endings, fields are quoted only when they contain a comma, quote or line break. NULL is empty.
+fn render_csv(result: &ResultSet) -> String {
+    let field = |text: &str| {
+        if text.contains([',', '"', '\n', '\r']) {
+            csv_quote(text)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut output = String::new();
+    let header: Vec<String> = result.columns.iter().map(|c| field(&c.name)).collect();
+    output += &header.join(",");
+    output += "\r\n";
+
+    for row in &result.rows {
+        let values: Vec<String> = row.iter().map(|value| field(&value_text(value))).collect();
+        output += &values.join(",");
+        output += "\r\n";
+    }
+
+    output
+}
+
+// Cells of the document formats, with NULL display and numeric locale applied.
+fn display_rows(result: &ResultSet, options: &PrintOptions) -> Vec<Vec<String>> {
+    let numeric: Vec<bool> = result.columns.iter().map(|c| is_numeric_type(&c.type_name)).collect();
+    result
+        .rows
+        .iter()
+        .map(|row| row.iter().enumerate().map(|(i, value)| display_text(value, numeric[i], options)).collect())
+        .collect()
+}
+
+// GitHub-flavored Markdown table, numbers right-aligned.
+fn render_markdown(result: &ResultSet, options: &PrintOptions) -> String {
+    if result.columns.is_empty() {
+        return String::new();
+    }
+
+    let escape = |text: &str| text.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>");
+    let header: Vec<String> = result.columns.iter().map(|c| escape(&c.name)).collect();
+    let rows: Vec<Vec<String>> = display_rows(result, options).iter().map(|row| row.iter().map(|c| escape(c)).collect()).collect();
+    let numeric: Vec<bool> = result.columns.iter().map(|c| is_numeric_type(&c.type_name)).collect();
+    // Markdown needs at least three dashes per column
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| rows.iter().map(|row| measure_text_width(&row[i])).chain([measure_text_width(name), 3]).max().unwrap_or(3))
+        .collect();
+
+    let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+    let mut output = line(header.iter().enumerate().map(|(i, name)| pad(name, widths[i], false)).collect());
+    output += &line(
+        widths
+            .iter()
+            .zip(&numeric)
+            .map(|(w, numeric)| if *numeric { format!("{}:", "-".repeat(w - 1)) } else { "-".repeat(*w) })
+            .collect(),
+    );
+    for row in &rows {
+        output += &line(row.iter().enumerate().map(|(i, cell)| pad(cell, widths[i], numeric[i])).collect());
+    }
+
+    output
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// HTML table, numbers right-aligned.
+fn render_html(result: &ResultSet, options: &PrintOptions) -> String {
+    let numeric: Vec<bool> = result.columns.iter().map(|c| is_numeric_type(&c.type_name)).collect();
+    let mut output = String::from("<table>\n  <thead>\n    <tr>");
+    for column in &result.columns {
+        output += &format!("<th>{}</th>", html_escape(&column.name));
+    }
+    output += "</tr>\n  </thead>\n  <tbody>\n";
+
+    for row in display_rows(result, options) {
+        output += "    <tr>";
+        for (i, cell) in row.iter().enumerate() {
+            let align = if numeric[i] { " align=\"right\"" } else { "" };
+            output += &format!("<td{}>{}</td>", align, html_escape(cell).replace('\n', "<br>"));
+        }
+        output += "</tr>\n";
+    }
+
+    output + "  </tbody>\n</table>\n"
+}
+
+// AsciiDoc table with a header row.
+fn render_asciidoc(result: &ResultSet, options: &PrintOptions) -> String {
+    let escape = |text: &str| text.replace('|', "\\|");
+    let cols: Vec<&str> = result.columns.iter().map(|c| if is_numeric_type(&c.type_name) { ">l" } else { "<l" }).collect();
+
+    let mut output = format!("[options=\"header\",cols=\"{}\"]\n|====\n", cols.join(","));
+    let header: Vec<String> = result.columns.iter().map(|c| format!("|{}", escape(&c.name))).collect();
+    output += &header.join(" ");
+    output += "\n";
+    for row in display_rows(result, options) {
+        let cells: Vec<String> = row.iter().map(|cell| format!("|{}", escape(cell))).collect();
+        output += &cells.join(" ");
+        output += "\n";
+    }
+
+    output + "|====\n"
+}
+
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped += "\\textbackslash{}",
+            '~' => escaped += "\\textasciitilde{}",
+            '^' => escaped += "\\textasciicircum{}",
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// LaTeX tabular environment, numbers right-aligned.
+fn render_latex(result: &ResultSet, options: &PrintOptions) -> String {
+    let spec: Vec<&str> = result.columns.iter().map(|c| if is_numeric_type(&c.type_name) { "r" } else { "l" }).collect();
+
+    let mut output = format!("\\begin{{tabular}}{{{}}}\n\\hline\n", spec.join(" | "));
+    let header: Vec<String> = result.columns.iter().map(|c| format!("\\textbf{{{}}}", latex_escape(&c.name))).collect();
+    output += &format!("{} \\\\\n\\hline\n", header.join(" & "));
+    for row in display_rows(result, options) {
+        let cells: Vec<String> = row.iter().map(|cell| latex_escape(cell)).collect();
+        output += &format!("{} \\\\\n", cells.join(" & "));
+    }
+
+    output + "\\hline\n\\end{tabular}\n"
+}
+
+// A JSON document with column metadata and one object per row.
+fn render_json(result: &ResultSet) -> String {
+    let meta: Vec<Value> = result.columns.iter().map(|c| json!({"name": c.name, "type": c.type_name})).collect();
+    let data: Vec<Value> = result
+        .rows
+        .iter()
+        .map(|row| Value::Object(result.columns.iter().zip(row).map(|(c, v)| (c.name.clone(), v.clone())).collect::<Map<_, _>>()))
+        .collect();
+
+    let mut document = json!({"meta": meta, "data": data, "rows": result.rows.len()});
+    if let Some(statistics) = &result.statistics {
+        document["statistics"] = statistics.clone();
+    }
+
+    serde_json::to_string_pretty(&document).unwrap_or_default() + "\n"
+}
+
+// The JSONLines_Compact messages the result was received as.
+fn render_jsonlines(result: &ResultSet) -> String {
+    let columns: Vec<Value> = result.columns.iter().map(|c| json!({"name": c.name, "type": c.type_name})).collect();
+    let mut output = json!({"message_type": "START", "result_columns": columns}).to_string() + "\n";
+    if !result.rows.is_empty() {
+        output += &(json!({"message_type": "DATA", "data": result.rows}).to_string() + "\n");
+    }
+    let mut finish = json!({"message_type": "FINISH_SUCCESSFULLY"});
+    if let Some(statistics) = &result.statistics {
+        finish["statistics"] = statistics.clone();
+    }
+    output + &finish.to_string() + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::Column;
+
+    fn sample() -> ResultSet {
+        ResultSet {
+            columns: vec![
+                Column { name: "id".to_string(), type_name: "integer".to_string() },
+                Column { name: "name".to_string(), type_name: "text null".to_string() },
+            ],
+            rows: vec![vec![json!(1), json!("alpha")], vec![json!(42), Value::Null]],
+            statistics: None,
+        }
+    }
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(Format::parse("PSQL"), Some(Format::Psql));
+        assert_eq!(Format::parse("csvwithnames"), Some(Format::CsvWithNames));
+        assert_eq!(Format::parse("CSV"), Some(Format::Csv));
+        assert_eq!(Format::for_session("markdown"), Some(Format::Markdown));
+        assert_eq!(Format::client_only("PSQL"), None);
+        assert_eq!(Format::parse("TabSeparatedWithNamesAndTypes"), None);
+        assert_eq!(Format::for_session("Vertical"), Some(Format::Vertical));
+        assert_eq!(Format::for_session("CSVWithNames"), None);
+    }
+
+    #[test]
+    fn test_render_psql() {
+        assert_eq!(render(&sample(), Format::Psql, &PrintOptions::default()), " id | name\n----+------\n  1 | alpha\n 42 |\n");
+
+        let single = ResultSet {
+            columns: vec![Column { name: "?column?".to_string(), type_name: "integer".to_string() }],
+            rows: vec![vec![json!(42)]],
+            statistics: None,
+        };
+        // Same layout as the server's PSQL output
+        assert_eq!(render(&single, Format::Psql, &PrintOptions::default()), " ?column?\n---------\n       42\n");
+        assert_eq!(render(&ResultSet::default(), Format::Psql, &PrintOptions::default()), "");
+    }
+
+    #[test]
+    fn test_local_format() {
+        let mut args = crate::args::get_args().unwrap();
+        args.extra = vec![];
+        args.max_result_memory = String::from("0");
+        args.format = String::from("PSQL");
+        assert_eq!(local_format(&args), None);
+        args.format = String::from("JSONLines_Compact");
+        assert_eq!(local_format(&args), Some(Format::JsonLines));
+        args.format = String::from("unaligned");
+        assert_eq!(local_format(&args), Some(Format::Unaligned));
+        args.format = String::from("markdown");
+        assert_eq!(local_format(&args), Some(Format::Markdown));
+
+        args.max_result_memory = String::from("64MB");
+        args.format = String::from("PSQL");
+        assert_eq!(local_format(&args), Some(Format::Psql));
+        args.format = String::from("Vertical");
+        assert_eq!(local_format(&args), Some(Format::Vertical));
+        args.format = String::from("TabSeparatedWithNames");
+        assert_eq!(local_format(&args), None);
+        args.format = String::from("PSQL");
+        args.extra = vec![String::from("format=JSON")];
+        assert_eq!(local_format(&args), None);
+
+        args.extra = vec![];
+        args.max_result_memory = String::from("0");
+        args.expanded = String::from("auto");
+        assert_eq!(local_format(&args), Some(Format::Psql));
+        args.expanded = String::from("off");
+        assert_eq!(local_format(&args), None);
+
+        args.pset = vec![String::from("null=∅")];
+        assert_eq!(local_format(&args), Some(Format::Psql));
+        args.pset.push(String::from("null"));
+        assert_eq!(local_format(&args), None);
+
+        args.template = String::from("{id}");
+        assert_eq!(local_format(&args), Some(Format::Psql));
+        args.format = String::from("TabSeparatedWithNames");
+        assert_eq!(local_format(&args), Some(Format::Psql));
+        args.template = String::new();
+        args.plot = String::from("bar");
+        assert_eq!(local_format(&args), Some(Format::Psql));
+        args.plot = String::new();
+        args.summarize = true;
+        assert_eq!(local_format(&args), Some(Format::Psql));
+
+        args.summarize = false;
+        args.format = String::from("Vertical");
+        args.max_rows = 10;
+        assert_eq!(local_format(&args), Some(Format::Vertical));
+    }
+
+    // Server output of the README examples, rendered locally from the same results
+    #[test]
+    fn test_parity_with_server_output() {
+        let body = "{\"message_type\":\"START\",\"result_columns\":[{\"name\":\"?column?\",\"type\":\"integer\"}]}\n\
+                    {\"message_type\":\"DATA\",\"data\":[[42]]}\n{\"message_type\":\"FINISH_SUCCESSFULLY\"}\n";
+        let Some(crate::result::Response::Result(result)) = crate::result::parse_response(body) else {
+            panic!("expected a result set");
+        };
+        assert_eq!(render(&result, Format::Psql, &PrintOptions::default()), " ?column?\n---------\n       42\n");
+
+        let body = "{\"message_type\":\"START\",\"result_columns\":[{\"name\":\"?column?\",\"type\":\"text\"}]}\n\
+                    {\"message_type\":\"DATA\",\"data\":[[\"qqq\"]]}\n{\"message_type\":\"FINISH_SUCCESSFULLY\"}\n";
+        let Some(crate::result::Response::Result(result)) = crate::result::parse_response(body) else {
+            panic!("expected a result set");
+        };
+        assert_eq!(render(&result, Format::Vertical, &PrintOptions::default()), "Row 1:\n──────\n?column?: qqq\n");
+    }
+
+    #[test]
+    fn test_render_vertical() {
+        assert_eq!(
+            render(&sample(), Format::Vertical, &PrintOptions::default()),
+            "Row 1:\n──────\nid:   1\nname: alpha\n\nRow 2:\n──────\nid:   42\nname: \n"
+        );
+    }
+
+    #[test]
+    fn test_render_text_formats() {
+        assert_eq!(render(&sample(), Format::TabSeparated, &PrintOptions::default()), "id\tname\n1\talpha\n42\t\\N\n");
+        assert_eq!(render(&sample(), Format::CsvWithNames, &PrintOptions::default()), "\"id\",\"name\"\n1,\"alpha\"\n42,\n");
+        assert_eq!(value_text(&json!(["a", "b c", null])), "{a,\"b c\",NULL}");
+        assert_eq!(value_text(&json!(true)), "t");
+    }
+
+    #[test]
+    fn test_render_json_formats() {
+        let json: Value = serde_json::from_str(&render(&sample(), Format::Json, &PrintOptions::default())).unwrap();
+        assert_eq!(json["rows"], 2);
+        assert_eq!(json["data"][0]["name"], "alpha");
+
+        let lines = render(&sample(), Format::JsonLines, &PrintOptions::default());
+        let parsed = crate::result::parse_response(&lines);
+        assert_eq!(parsed, Some(crate::result::Response::Result(sample())));
+    }
+
+    #[test]
+    fn test_render_display() {
+        assert_eq!(Expanded::parse("AUTO"), Some(Expanded::Auto));
+        assert_eq!(Expanded::parse("sideways"), None);
+
+        let options = PrintOptions::default();
+        assert_eq!(render_display(&sample(), Format::Psql, Expanded::On, &options), render(&sample(), Format::Vertical, &options));
+        assert_eq!(render_display(&sample(), Format::Psql, Expanded::Off, &options), render(&sample(), Format::Psql, &options));
+        // Only tables are affected
+        assert_eq!(render_display(&sample(), Format::Csv, Expanded::On, &options), render(&sample(), Format::Csv, &options));
+    }
+
+    #[test]
+    fn test_print_options() {
+        let mut options = PrintOptions::from_settings(&["null=∅".to_string(), "footer".to_string()]).unwrap();
+        assert_eq!(options.null, "∅");
+        assert!(options.footer);
+
+        options.set("fieldsep", Some("\\t")).unwrap();
+        assert_eq!(options.fieldsep, "\t");
+        assert_eq!(options.get("fieldsep"), Some("\\t".to_string()));
+        options.set("fieldsep", None).unwrap();
+        assert_eq!(options.fieldsep, "|");
+
+        assert!(options.set("border", Some("3")).is_err());
+        assert!(options.set("linestyle", Some("fancy")).is_err());
+        assert!(options.set("footer", Some("maybe")).is_err());
+        assert!(options.set("colour", Some("red")).is_err());
+    }
+
+    #[test]
+    fn test_render_with_print_options() {
+        let mut options = PrintOptions::from_settings(&["null=∅".to_string(), "footer=on".to_string()]).unwrap();
+        assert_eq!(render(&sample(), Format::Psql, &options), " id | name\n----+------\n  1 | alpha\n 42 | ∅\n(2 rows)\n");
+
+        options.set("linestyle", Some("unicode")).unwrap();
+        options.set("border", Some("2")).unwrap();
+        assert_eq!(
+            render(&sample(), Format::Psql, &options),
+            "┌────┬───────┐\n│ id │ name  │\n├────┼───────┤\n│  1 │ alpha │\n│ 42 │ ∅     │\n└────┴───────┘\n(2 rows)\n"
+        );
+
+        options.set("border", Some("0")).unwrap();
+        options.set("tuples_only", Some("on")).unwrap();
+        assert_eq!(render(&sample(), Format::Psql, &options), " 1 alpha\n42 ∅\n");
+        assert_eq!(render(&sample(), Format::Vertical, &options), "id:   1\nname: alpha\n\nid:   42\nname: ∅\n");
+
+        let options = PrintOptions::from_settings(&["fieldsep=,".to_string(), "footer=on".to_string()]).unwrap();
+        assert_eq!(render(&sample(), Format::Unaligned, &options), "id,name\n1,alpha\n42,\n(2 rows)\n");
+    }
+
+    #[test]
+    fn test_numeric_locale() {
+        assert_eq!(group_thousands("1234567"), "1,234,567");
+        assert_eq!(group_thousands("-1234.5678"), "-1,234.5678");
+        assert_eq!(group_thousands("123"), "123");
+        assert_eq!(group_thousands("12ab"), "12ab");
+
+        let options = PrintOptions::from_settings(&["numericlocale=on".to_string()]).unwrap();
+        let result = ResultSet {
+            columns: vec![Column { name: "n".to_string(), type_name: "bigint".to_string() }],
+            rows: vec![vec![json!(1234567)]],
+            statistics: None,
+        };
+        assert_eq!(render(&result, Format::Psql, &options), "     n\n----------\n 1,234,567\n");
+    }
+
+    fn document_sample() -> ResultSet {
+        ResultSet {
+            columns: vec![
+                Column { name: "id".to_string(), type_name: "integer".to_string() },
+                Column { name: "note".to_string(), type_name: "text null".to_string() },
+            ],
+            rows: vec![vec![json!(1), json!("a|b, \"c\" & <d>_%")], vec![json!(2), Value::Null]],
+            statistics: None,
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        assert_eq!(render(&document_sample(), Format::Csv, &PrintOptions::default()), "id,note\r\n1,\"a|b, \"\"c\"\" & <d>_%\"\r\n2,\r\n");
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let options = PrintOptions::from_settings(&["null=NULL".to_string()]).unwrap();
+        assert_eq!(
+            render(&document_sample(), Format::Markdown, &options),
+            "| id  | note              |\n| --: | ----------------- |\n|   1 | a\\|b, \"c\" & <d>_% |\n|   2 | NULL              |\n"
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        assert_eq!(
+            render(&document_sample(), Format::Html, &PrintOptions::default()),
+            "<table>\n  <thead>\n    <tr><th>id</th><th>note</th></tr>\n  </thead>\n  <tbody>\n\
+             \x20   <tr><td align=\"right\">1</td><td>a|b, &quot;c&quot; &amp; &lt;d&gt;_%</td></tr>\n\
+             \x20   <tr><td align=\"right\">2</td><td></td></tr>\n  </tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_asciidoc_and_latex() {
+        assert_eq!(
+            render(&document_sample(), Format::AsciiDoc, &PrintOptions::default()),
+            "[options=\"header\",cols=\">l,<l\"]\n|====\n|id |note\n|1 |a\\|b, \"c\" & <d>_%\n|2 |\n|====\n"
+        );
+        assert_eq!(
+            render(&document_sample(), Format::Latex, &PrintOptions::default()),
+            "\\begin{tabular}{r | l}\n\\hline\n\\textbf{id} & \\textbf{note} \\\\\n\\hline\n\
+             1 & a|b, \"c\" \\& <d>\\_\\% \\\\\n2 &  \\\\\n\\hline\n\\end{tabular}\n"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use serde_json::Value;
+
+// Output format requested from the server when results are rendered locally; it keeps names, types and values intact.
+pub const LOSSLESS_FORMAT: &str = "JSONLines_Compact";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub type_name: String,
+}
+
+// Result set of a single statement, as received from the server.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultSet {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+    pub statistics: Option<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Response {
+    Result(ResultSet),
+    Errors(Vec<String>),
+}
+
+// Parse a JSONLines_Compact response. Returns None when the body is not in that format.
+pub fn parse_response(body: &str) -> Option<Response> {
+    let mut result = ResultSet::default();
+    let mut errors: Vec<String> = vec![];
+    let mut seen_message = false;
+
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        let message: Value = serde_json::from_str(line).ok()?;
+        seen_message = true;
+
+        match message.get("message_type")?.as_str()? {
+            "START" => {
+                let columns = message.get("result_columns").and_then(Value::as_array).cloned().unwrap_or_default();
+                result.columns = columns
+                    .iter()
+                    .map(|column| Column {
+                        name: column.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+                        type_name: column.get("type").and_then(Value::as_str).unwrap_or_default().to_string(),
+                    })
+                    .collect();
+            }
+            "DATA" => {
+                let rows = message.get("data").and_then(Value::as_array).cloned().unwrap_or_default();
+                result.rows.extend(rows.into_iter().map(|row| match row {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                }));
+            }
+            "FINISH_SUCCESSFULLY" => result.statistics = message.get("statistics").cloned(),
+            "FINISH_WITH_ERRORS" => {
+                let list = message.get("errors").and_then(Value::as_array).cloned().unwrap_or_default();
+                errors.extend(list.iter().map(|error| match error.get("description").and_then(Value::as_str) {
+                    Some(description) => description.to_string(),
+                    None => error.to_string(),
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    if !seen_message {
+        return None;
+    }
+
+    if errors.is_empty() {
+        Some(Response::Result(result))
+    } else {
+        Some(Response::Errors(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let body = concat!(
+            r#"{"message_type":"START","result_columns":[{"name":"id","type":"integer"},{"name":"name","type":"text null"}]}"#,
+            "\n",
+            r#"{"message_type":"DATA","data":[[1,"a"],[2,null]]}"#,
+            "\n",
+            r#"{"message_type":"DATA","data":[[3,"c"]]}"#,
+            "\n",
+            r#"{"message_type":"FINISH_SUCCESSFULLY","statistics":{"elapsed":0.01,"rows_read":3}}"#,
+            "\n"
+        );
+
+        let Some(Response::Result(result)) = parse_response(body) else {
+            panic!("expected a result set");
+        };
+        assert_eq!(result.columns.len(), 2);
+        assert_eq!(result.columns[1].type_name, "text null");
+        assert_eq!(result.rows.len(), 3);
+        assert_eq!(result.rows[1][1], Value::Null);
+        assert_eq!(result.statistics.unwrap()["rows_read"], 3);
+    }
+
+    #[test]
+    fn test_parse_errors_and_other_formats() {
+        let body = r#"{"message_type":"FINISH_WITH_ERRORS","errors":[{"description":"syntax error"}]}"#;
+        assert_eq!(parse_response(body), Some(Response::Errors(vec!["syntax error".to_string()])));
+
+        assert_eq!(parse_response("?column?\n42\n"), None);
+        assert_eq!(parse_response(""), None);
+    }
+}
@@ -0,0 +1,244 @@
+use std::fs;
+use std::path::Path;
+
+use crate::async_query::run_statement;
+use crate::context::Context;
+use crate::meta_commands::{handle_meta_command, is_conditional_command, unknown_command_message};
+use crate::query::try_split_queries;
+
+// Run SQL statements and meta-commands from a file in the current context.
+pub async fn run_file(context: &mut Context, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let canonical = fs::canonicalize(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+
+    if context.include_stack.contains(&canonical) {
+        let chain: Vec<String> = context
+            .include_stack
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(format!("Include cycle detected: {}", chain.join(" -> ")).into());
+    }
+
+    let text = fs::read_to_string(&canonical)?;
+
+    context.include_stack.push(canonical);
+    // Keep the buffer of the including session aside while the file runs
+    let outer_buffer = std::mem::take(&mut context.query_buffer);
+    let depth = context.conditionals.depth();
+    let result = run_script(context, &text, path).await;
+    context.conditionals.truncate(depth);
+    context.query_buffer = outer_buffer;
+    context.include_stack.pop();
+
+    result
+}
+
+// Outcome of feeding one input line to the statement loop.
+pub enum LineResult {
+    // The line was buffered, skipped or ran as a meta-command
+    Consumed,
+    // The buffer holds complete statements that should run now
+    Statements(Vec<String>),
+    UnknownCommand,
+}
+
+// Feed one line to the statement loop shared by the REPL and scripts. Meta-commands run right away,
+// lines inside skipped \if branches are dropped and SQL accumulates in `buffer` until complete.
+pub async fn process_line(context: &mut Context, buffer: &mut String, line: &str) -> Result<LineResult, Box<dyn std::error::Error>> {
+    let trimmed = line.trim();
+
+    if trimmed.starts_with('\\') {
+        if context.conditionals.is_skipping() && !is_conditional_command(trimmed) {
+            return Ok(LineResult::Consumed);
+        }
+
+        // Meta-commands see the statement typed so far and may consume it
+        context.query_buffer = std::mem::take(buffer);
+        // Boxed because included files may include further files
+        let result = Box::pin(handle_meta_command(context, trimmed)).await;
+        *buffer = std::mem::take(&mut context.query_buffer);
+
+        return match result? {
+            true => Ok(LineResult::Consumed),
+            false => Ok(LineResult::UnknownCommand),
+        };
+    }
+
+    if context.conditionals.is_skipping() {
+        return Ok(LineResult::Consumed);
+    }
+
+    *buffer += line;
+    *buffer += "\n";
+
+    Ok(match try_split_queries(buffer) {
+        Some(queries) => LineResult::Statements(queries),
+        None => LineResult::Consumed,
+    })
+}
+
+// Execute script text line by line, the way the REPL does. Errors carry a "file:line" trace.
+async fn run_script(context: &mut Context, text: &str, source: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let at = |e: Box<dyn std::error::Error>, line: usize| -> Box<dyn std::error::Error> {
+        format!("{}\n  at {}:{}", e, source.display(), line).into()
+    };
+
+    let depth = context.conditionals.depth();
+    let mut buffer = String::new();
+    let mut buffer_line = 1;
+    let mut line_count = 0;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        line_count = line_number;
+
+        if buffer.trim().is_empty() {
+            buffer_line = line_number;
+        }
+
+        match process_line(context, &mut buffer, line).await.map_err(|e| at(e, line_number))? {
+            LineResult::Consumed => {}
+            LineResult::Statements(queries) => {
+                run_queries(context, &buffer, buffer_line, queries).await.map_err(|(e, line)| at(e, line))?;
+                buffer.clear();
+            }
+            LineResult::UnknownCommand => return Err(at(unknown_command_message(line.trim()).into(), line_number)),
+        }
+    }
+
+    // Each file must close the \if blocks it opens
+    if context.conditionals.depth() > depth {
+        let unterminated = context.conditionals.depth() - depth;
+        return Err(at(format!("Reached end of file with {} unterminated \\if block(s)", unterminated).into(), line_count));
+    }
+
+    // Like the REPL on EOF, a trailing statement without a semicolon still runs
+    if !buffer.trim().is_empty() {
+        buffer += ";";
+        if let Some(queries) = try_split_queries(&buffer) {
+            run_queries(context, &buffer, buffer_line, queries).await.map_err(|(e, line)| at(e, line))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Run split queries, reporting the line each failing statement starts on.
+async fn run_queries(
+    context: &mut Context,
+    buffer: &str,
+    buffer_line: usize,
+    queries: Vec<String>,
+) -> Result<(), (Box<dyn std::error::Error>, usize)> {
+    let mut offset = 0;
+    for q in queries {
+        let start = buffer[offset..].find(q.as_str()).map_or(offset, |pos| offset + pos);
+        let leading = q.len() - q.trim_start().len();
+        let line = buffer_line + buffer[..start + leading].matches('\n').count();
+        offset = start + q.len();
+
+        run_statement(context, q).await.map_err(|e| (e, line))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::get_args;
+
+    fn script_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("fb-script-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_run_file_with_nested_includes() {
+        let dir = script_dir("nested");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let inner = dir.join("sub").join("inner.sql");
+        fs::write(dir.join("main.sql"), format!("set first = 1;\n\\i '{}'\n", inner.display())).unwrap();
+        fs::write(&inner, "-- comment\nset second = 2;\n\\ir last.sql\n").unwrap();
+        fs::write(dir.join("sub").join("last.sql"), "set third = 3").unwrap();
+
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        run_file(&mut context, &dir.join("main.sql")).await.unwrap();
+
+        assert!(context.args.extra.iter().any(|e| e == "first=1"));
+        assert!(context.args.extra.iter().any(|e| e == "second=2"));
+        assert!(context.args.extra.iter().any(|e| e == "third=3"));
+        assert!(context.include_stack.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_file_detects_cycles() {
+        let dir = script_dir("cycle");
+        fs::write(dir.join("a.sql"), "set a = 1;\n\\ir b.sql\n").unwrap();
+        fs::write(dir.join("b.sql"), "\n\\ir a.sql\n").unwrap();
+
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        let error = run_file(&mut context, &dir.join("a.sql")).await.unwrap_err().to_string();
+
+        assert!(error.starts_with("Include cycle detected"), "{}", error);
+        assert!(error.contains("b.sql:2"), "{}", error);
+        assert!(error.contains("a.sql:2"), "{}", error);
+        assert!(context.include_stack.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_file_reports_failing_line() {
+        let dir = script_dir("failing");
+        fs::write(dir.join("bad.sql"), "set a = 1;\n\n\\unknown\n").unwrap();
+
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        let error = run_file(&mut context, &dir.join("bad.sql")).await.unwrap_err().to_string();
+
+        assert!(error.contains("Invalid command \\unknown"), "{}", error);
+        assert!(error.contains("bad.sql:3"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_run_file_with_conditionals() {
+        let dir = script_dir("conditionals");
+        fs::write(
+            dir.join("branches.sql"),
+            "\\if true\nset a = 1;\n\\if :missing\nset never = 1;\n\\endif\n\\elif true\nset never = 2;\n\\else\n\\unknown\n\\endif\n\
+             \\if no\n\\i missing.sql\n\\else\nset b = 2;\n\\endif\n",
+        )
+        .unwrap();
+
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        let error = run_file(&mut context, &dir.join("branches.sql")).await.unwrap_err().to_string();
+        // The invalid condition fails the script, like a failing statement
+        assert!(error.contains("Boolean expected"), "{}", error);
+        assert!(error.contains("branches.sql:3"), "{}", error);
+        assert_eq!(context.conditionals.depth(), 0);
+
+        context.variables.insert("missing".to_string(), "false".to_string());
+        run_file(&mut context, &dir.join("branches.sql")).await.unwrap();
+        assert!(context.args.extra.iter().any(|e| e == "a=1"));
+        assert!(context.args.extra.iter().any(|e| e == "b=2"));
+        assert!(!context.args.extra.iter().any(|e| e.starts_with("never=")));
+    }
+
+    #[tokio::test]
+    async fn test_run_file_unterminated_if() {
+        let dir = script_dir("unterminated");
+        fs::write(dir.join("open.sql"), "\\if yes\nset a = 1;\n").unwrap();
+
+        let mut context = Context::new(get_args().unwrap());
+        context.args.concise = true;
+        let error = run_file(&mut context, &dir.join("open.sql")).await.unwrap_err().to_string();
+
+        assert!(error.contains("1 unterminated \\if block"), "{}", error);
+        assert!(error.contains("open.sql:2"), "{}", error);
+        assert_eq!(context.conditionals.depth(), 0);
+    }
+}
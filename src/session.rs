@@ -0,0 +1,252 @@
+use std::time::SystemTime;
+
+use crate::auth::{engine_exists, load_saved_credentials, maybe_authenticate, resolve_engine_endpoint, system_engine_host};
+use crate::context::{initial_setting_sources, Context};
+use crate::utils::{credentials_path, format_remaining_time};
+use crate::FIREBOLT_PROTOCOL_VERSION;
+
+// Target of a \c command; None keeps the current value.
+#[derive(Debug, Default, PartialEq)]
+pub struct ConnectTarget {
+    pub profile: Option<String>,
+    pub database: Option<String>,
+    pub engine: Option<String>,
+}
+
+// Parse \c arguments: [--profile NAME] [DATABASE|-] [ENGINE|-]
+pub fn parse_connect_args(args: &str) -> Result<ConnectTarget, Box<dyn std::error::Error>> {
+    let mut target = ConnectTarget::default();
+    let mut positional: Vec<String> = vec![];
+
+    let mut words = args.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == "--profile" {
+            target.profile = Some(words.next().ok_or("Missing profile name after --profile")?.to_string());
+        } else if let Some(profile) = word.strip_prefix("--profile=") {
+            target.profile = Some(profile.to_string());
+        } else if word.starts_with("--") {
+            return Err(format!("Unknown option: {}", word).into());
+        } else {
+            positional.push(word.to_string());
+        }
+    }
+
+    if positional.len() > 2 {
+        return Err("Usage: \\c [--profile NAME] [DATABASE|-] [ENGINE|-]".into());
+    }
+
+    let mut positional = positional.into_iter().map(|p| if p == "-" { None } else { Some(p) });
+    target.database = positional.next().flatten();
+    target.engine = positional.next().flatten();
+
+    Ok(target)
+}
+
+// Engine the session is connected to, from the endpoint or the engine parameter.
+pub fn current_engine(context: &Context) -> Option<String> {
+    if let Some(pos) = context.args.host.find("?engine=") {
+        let engine = &context.args.host[pos + "?engine=".len()..];
+        return Some(engine.split('&').next().unwrap_or_default().to_string());
+    }
+
+    context.args.extra.iter().find_map(|e| e.strip_prefix("engine=").map(|engine| engine.to_string()))
+}
+
+// Values that may contain PII are masked with --hide-pii.
+fn mask(value: &str, hide_pii: bool) -> String {
+    if hide_pii && !value.is_empty() {
+        String::from("****")
+    } else {
+        value.to_string()
+    }
+}
+
+// Describe how the session authenticates.
+fn auth_method(context: &Context) -> String {
+    if !context.args.sa_id.is_empty() {
+        format!("Service Account ({})", mask(&context.args.sa_id, context.args.hide_pii))
+    } else if !context.args.jwt.is_empty() {
+        String::from("JWT")
+    } else if context.auth_token.is_some() {
+        String::from("Browser login")
+    } else {
+        String::from("none")
+    }
+}
+
+// Print a summary of the current connection.
+pub fn print_conninfo(context: &Context) {
+    let hide_pii = context.args.hide_pii;
+    let profile = if context.args.profile.is_empty() { "default" } else { context.args.profile.as_str() };
+    println!("Profile: {}", profile);
+    if !context.args.account_name.is_empty() {
+        println!("Account: {}", mask(&context.args.account_name, hide_pii));
+    }
+    println!("Auth method: {}", auth_method(context));
+    if let Some(token) = &context.auth_token {
+        let valid_until = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(token.until);
+        match format_remaining_time(valid_until, "".into()) {
+            Ok(remaining) => println!("Token valid for: {}", remaining),
+            Err(_) => println!("Token: expired"),
+        }
+    }
+    println!("Host: {}", mask(&context.args.host, hide_pii));
+    println!("Engine: {}", current_engine(context).unwrap_or_else(|| String::from("system")));
+    if context.args.database.is_empty() {
+        println!("Database: (none)");
+    } else {
+        println!("Database: {}", mask(&context.args.database, hide_pii));
+    }
+    println!("Protocol version: {}", FIREBOLT_PROTOCOL_VERSION);
+}
+
+// Print session settings along with where each one came from.
+pub fn print_settings(context: &Context) {
+    if context.args.extra.is_empty() {
+        println!("No settings.");
+        return;
+    }
+
+    let width = context.args.extra.iter().map(|e| e.split('=').next().unwrap_or_default().len()).max().unwrap_or(0);
+    for setting in &context.args.extra {
+        let (key, value) = setting.split_once('=').unwrap_or((setting.as_str(), ""));
+        let value = urlencoding::decode(value).map_or_else(|_| value.to_string(), |v| v.into_owned());
+        let source = context.setting_sources.get(key).map_or_else(|| String::from("unknown"), |s| s.to_string());
+        println!("{:width$} = {} ({})", key, mask(&value, context.args.hide_pii), source);
+    }
+}
+
+// Switch to another credentials profile and re-authenticate if the identity changes.
+async fn switch_profile(context: &mut Context, profile: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let profile = if profile == "default" { "" } else { profile };
+    if !credentials_path(profile)?.exists() {
+        return Err(format!("No saved credentials for profile '{}'. Run 'fb auth --profile {}' first.", profile, profile).into());
+    }
+
+    // Forget the previous profile's connection settings and token before loading the new ones. A token is only valid
+    // for the account, environment and credentials it was issued for, so it is never carried over.
+    context.args.profile = profile.to_string();
+    context.args.host.clear();
+    context.args.database.clear();
+    context.args.sa_id.clear();
+    context.args.sa_secret.clear();
+    context.args.jwt.clear();
+    context.saved_creds = None;
+    context.auth_token = None;
+    load_saved_credentials(&mut context.args)?;
+    context.update_url();
+    maybe_authenticate(context).await
+}
+
+// Context on the system engine of the current account, for internal queries.
+pub fn system_context(context: &Context) -> Context {
+    let mut system_args = context.args.clone();
+    system_args.host = system_engine_host(&context.args.host);
+    system_args.extra = context.initial_extra.clone();
+    system_args.format = String::from("TabSeparatedWithNames");
+    system_args.concise = true;
+    system_args.verbose = false;
+    // Starting an engine does not modify data
+    system_args.read_only = false;
+    system_args.stats_json.clear();
+
+    let mut system_context = Context::new(system_args);
+    system_context.auth_token = context.auth_token.clone();
+    system_context
+}
+
+// Resolve an engine endpoint through the system engine of the current account.
+async fn resolve_engine(context: &Context, engine_name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut system_context = system_context(context);
+
+    if !engine_exists(&mut system_context, engine_name).await {
+        return Err(format!("Engine '{}' does not exist. Run 'fb show engines' to see available engines.", engine_name).into());
+    }
+
+    resolve_engine_endpoint(&mut system_context, engine_name).await
+}
+
+// Switch database, engine or profile in place; session parameters are reset to their initial values.
+pub async fn connect(context: &mut Context, target: ConnectTarget) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(profile) = &target.profile {
+        switch_profile(context, profile).await?;
+    }
+
+    if let Some(engine) = &target.engine {
+        context.args.host = resolve_engine(context, engine).await?;
+    }
+
+    context.args.extra = context.initial_extra.clone();
+    context.setting_sources = initial_setting_sources(&context.initial_extra, &context.config_extra);
+    if let Some(database) = target.database {
+        context.args.database = database;
+    }
+    context.update_url();
+
+    print_conninfo(context);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::get_args;
+
+    #[test]
+    fn test_parse_connect_args() {
+        assert_eq!(parse_connect_args("").unwrap(), ConnectTarget::default());
+
+        let target = parse_connect_args("my_db my_engine").unwrap();
+        assert_eq!(target.database, Some("my_db".to_string()));
+        assert_eq!(target.engine, Some("my_engine".to_string()));
+        assert_eq!(target.profile, None);
+
+        let target = parse_connect_args("--profile prod - my_engine").unwrap();
+        assert_eq!(target.profile, Some("prod".to_string()));
+        assert_eq!(target.database, None);
+        assert_eq!(target.engine, Some("my_engine".to_string()));
+
+        let target = parse_connect_args("--profile=dev").unwrap();
+        assert_eq!(target.profile, Some("dev".to_string()));
+
+        assert!(parse_connect_args("--profile").is_err());
+        assert!(parse_connect_args("--unknown").is_err());
+        assert!(parse_connect_args("a b c").is_err());
+    }
+
+    #[test]
+    fn test_current_engine() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.host = "api.firebolt.io?engine=my_engine".to_string();
+        assert_eq!(current_engine(&context), Some("my_engine".to_string()));
+
+        context.args.host = "localhost:3473".to_string();
+        context.args.extra = vec!["engine=other".to_string()];
+        assert_eq!(current_engine(&context), Some("other".to_string()));
+
+        context.args.extra.clear();
+        assert_eq!(current_engine(&context), None);
+    }
+
+    #[tokio::test]
+    async fn test_connect_resets_session_parameters() {
+        let mut context = Context::new(get_args().unwrap());
+        context.args.extra = vec!["transaction_id=42".to_string(), "custom=1".to_string()];
+        context.update_url();
+
+        connect(&mut context, parse_connect_args("other_db").unwrap()).await.unwrap();
+
+        assert_eq!(context.args.database, "other_db");
+        assert_eq!(context.args.extra, context.initial_extra);
+        assert!(context.url.contains("database=other_db"));
+        assert!(!context.url.contains("transaction_id"));
+    }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask("secret", true), "****");
+        assert_eq!(mask("secret", false), "secret");
+        assert_eq!(mask("", true), "");
+    }
+}
@@ -2,11 +2,12 @@ use crate::utils::credentials_path;
 use std::fs;
 
 /// Load saved credentials and create a context for internal queries
-async fn create_query_context(
+pub(crate) async fn create_query_context(
+    profile: &str,
     database: Option<String>,
     format: Option<String>,
 ) -> Result<crate::context::Context, Box<dyn std::error::Error>> {
-    let creds_path = credentials_path()?;
+    let creds_path = credentials_path(profile)?;
     if !creds_path.exists() {
         return Err("No saved credentials found. Run 'fb auth' first.".into());
     }
@@ -26,6 +27,7 @@ async fn create_query_context(
     };
 
     crate::auth::create_context_from_credentials(
+        profile,
         system_engine_host,
         database.unwrap_or_default(),
         format.unwrap_or_else(|| String::from("PSQL")),
@@ -36,8 +38,8 @@ async fn create_query_context(
 }
 
 /// Show available databases
-pub async fn show_databases() -> Result<(), Box<dyn std::error::Error>> {
-    let mut context = create_query_context(None, None).await?;
+pub async fn show_databases(profile: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut context = create_query_context(profile, None, None).await?;
 
     println!("Available databases:\n");
     let query = "SELECT catalog_name FROM information_schema.catalogs ORDER BY catalog_name";
@@ -47,8 +49,8 @@ pub async fn show_databases() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// Show available engines
-pub async fn show_engines() -> Result<(), Box<dyn std::error::Error>> {
-    let mut context = create_query_context(None, None).await?;
+pub async fn show_engines(profile: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut context = create_query_context(profile, None, None).await?;
 
     println!("Available engines:\n");
     let query = "SELECT engine_name, status FROM information_schema.engines ORDER BY status, engine_name";
@@ -0,0 +1,170 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::context::Context;
+use crate::result::{parse_response, Response};
+
+// Hex digits of the statement hash that are kept.
+const HASH_LENGTH: usize = 16;
+
+// One line of --stats-json output, for a single executed statement.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StatementStats {
+    pub index: usize,
+    pub statement_hash: String,
+    pub started_at: String,
+    pub duration_ms: f64,
+    pub http_status: Option<u16>,
+    pub request_id: Option<String>,
+    pub bytes_received: usize,
+    pub rows: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl StatementStats {
+    pub fn start(statement: &str) -> Self {
+        let hash = format!("{:x}", Sha256::digest(statement.trim().as_bytes()));
+        Self { statement_hash: hash[..HASH_LENGTH].to_string(), started_at: format_timestamp(SystemTime::now()), ..Default::default() }
+    }
+
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration_ms = (duration.as_secs_f64() * 1000.0 * 1000.0).round() / 1000.0;
+    }
+
+    // Fill rows, size and error from a response body.
+    pub fn set_response(&mut self, status: u16, body: &str) {
+        self.http_status = Some(status);
+        self.bytes_received = body.len();
+
+        match parse_response(body) {
+            Some(Response::Result(result)) => self.rows = Some(result.rows.len() as u64),
+            Some(Response::Errors(errors)) => self.error = Some(errors.join("; ")),
+            // JSON output carries the row count, other formats do not
+            None => {
+                if let Ok(json) = serde_json::from_str::<Value>(body) {
+                    self.rows = json.get("rows").and_then(Value::as_u64);
+                }
+            }
+        }
+
+        if self.error.is_none() && !(200..300).contains(&status) {
+            let message = body.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+            self.error = Some(if message.is_empty() { format!("HTTP status {}", status) } else { message.to_string() });
+        }
+    }
+}
+
+// RFC 3339 UTC timestamp with milliseconds.
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() as i64;
+    let (days, day_seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Civil date from days since the epoch
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        day_seconds / 3600,
+        day_seconds % 3600 / 60,
+        day_seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// Append statistics of a statement to --stats-json: a file, created on first use, or stdout for "-".
+pub fn write_stats(context: &mut Context, mut stats: StatementStats) -> Result<(), Box<dyn std::error::Error>> {
+    context.statements_run += 1;
+    stats.index = context.statements_run;
+    let line = format!("{}\n", serde_json::to_string(&stats)?);
+
+    if context.args.stats_json == "-" {
+        print!("{}", line);
+        std::io::stdout().flush()?;
+        return Ok(());
+    }
+
+    if context.stats_file.is_none() {
+        let path = &context.args.stats_json;
+        context.stats_file = Some(File::create(path).map_err(|e| format!("Cannot open {}: {}", path, e))?);
+    }
+    if let Some(file) = context.stats_file.as_mut() {
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::get_args;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let time = UNIX_EPOCH + Duration::from_millis(1_707_268_759_815);
+        assert_eq!(format_timestamp(time), "2024-02-07T01:19:19.815Z");
+        let time = UNIX_EPOCH + Duration::from_secs(951782400);
+        assert_eq!(format_timestamp(time), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_statement_stats() {
+        let mut stats = StatementStats::start("select 1;\n");
+        assert_eq!(stats.statement_hash.len(), HASH_LENGTH);
+        assert_eq!(stats.statement_hash, StatementStats::start("select 1;").statement_hash);
+
+        let body = concat!(
+            r#"{"message_type":"START","result_columns":[{"name":"a","type":"int"}]}"#,
+            "\n",
+            r#"{"message_type":"DATA","data":[[1],[2]]}"#,
+        );
+        stats.set_response(200, body);
+        stats.set_duration(Duration::from_micros(12345));
+        assert_eq!(stats.rows, Some(2));
+        assert_eq!(stats.error, None);
+        assert_eq!(stats.duration_ms, 12.345);
+
+        let mut stats = StatementStats::start("select x");
+        stats.set_response(400, r#"{"message_type":"FINISH_WITH_ERRORS","errors":[{"description":"column x not found"}]}"#);
+        assert_eq!(stats.error.as_deref(), Some("column x not found"));
+
+        let mut stats = StatementStats::start("select 1");
+        stats.set_response(500, "\nInternal error\ndetails");
+        assert_eq!(stats.error.as_deref(), Some("Internal error"));
+        assert_eq!(stats.rows, None);
+    }
+
+    #[test]
+    fn test_write_stats() {
+        let path = std::env::temp_dir().join(format!("fb-stats-{}.jsonl", std::process::id()));
+        let mut context = Context::new(get_args().unwrap());
+        context.args.stats_json = path.display().to_string();
+
+        write_stats(&mut context, StatementStats::start("select 1")).unwrap();
+        write_stats(&mut context, StatementStats::start("select 2")).unwrap();
+
+        let lines: Vec<Value> =
+            std::fs::read_to_string(&path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["index"], 2);
+        assert_eq!(lines[1]["http_status"], Value::Null);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::plot::{format_number, number};
+use crate::render::{is_numeric_type, value_text};
+use crate::result::{Column, ResultSet};
+
+// Distinct values are counted exactly up to this many, then estimated.
+const EXACT_DISTINCT_LIMIT: usize = 10_000;
+// Smallest hashes kept for the k-minimum-values distinct estimate.
+const KMV_SIZE: usize = 1024;
+// Counters of the space-saving sketch that finds top values once counting is no longer exact.
+const TOP_COUNTERS: usize = 100;
+// Top values shown per column, and their longest shown length.
+const TOP_VALUES: usize = 3;
+const TOP_VALUE_WIDTH: usize = 20;
+
+#[derive(Default)]
+struct ColumnStats {
+    nulls: usize,
+    // Exact counts per value, dropped when there are too many distinct values
+    exact: Option<HashMap<String, usize>>,
+    sketch: HashMap<String, usize>,
+    kmv: BTreeSet<u64>,
+    min_number: Option<f64>,
+    max_number: Option<f64>,
+    min_text: Option<String>,
+    max_text: Option<String>,
+    sum: f64,
+    count: usize,
+}
+
+fn hash(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ColumnStats {
+    fn new() -> Self {
+        Self { exact: Some(HashMap::new()), ..Default::default() }
+    }
+
+    fn observe(&mut self, value: &Value, numeric: bool) {
+        if value.is_null() {
+            self.nulls += 1;
+            return;
+        }
+
+        let key = value_text(value);
+        self.kmv.insert(hash(&key));
+        if self.kmv.len() > KMV_SIZE {
+            self.kmv.pop_last();
+        }
+
+        match numeric.then(|| number(value)).flatten() {
+            Some(n) => {
+                self.min_number = Some(self.min_number.map_or(n, |m| m.min(n)));
+                self.max_number = Some(self.max_number.map_or(n, |m| m.max(n)));
+                self.sum += n;
+                self.count += 1;
+            }
+            None => {
+                if self.min_text.as_ref().is_none_or(|m| key < *m) {
+                    self.min_text = Some(key.clone());
+                }
+                if self.max_text.as_ref().is_none_or(|m| key > *m) {
+                    self.max_text = Some(key.clone());
+                }
+            }
+        }
+
+        match &mut self.exact {
+            Some(counts) => {
+                *counts.entry(key).or_default() += 1;
+                if counts.len() > EXACT_DISTINCT_LIMIT {
+                    // Continue with the most frequent values so far
+                    let mut top: Vec<(String, usize)> = counts.drain().collect();
+                    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                    self.sketch = top.into_iter().take(TOP_COUNTERS).collect();
+                    self.exact = None;
+                }
+            }
+            None => self.observe_top(key),
+        }
+    }
+
+    // Space-saving: a new value replaces the least counted one and inherits its count.
+    fn observe_top(&mut self, key: String) {
+        if let Some(count) = self.sketch.get_mut(&key) {
+            *count += 1;
+            return;
+        }
+        if self.sketch.len() < TOP_COUNTERS {
+            self.sketch.insert(key, 1);
+            return;
+        }
+        let (min_key, min_count) = self.sketch.iter().min_by_key(|(_, count)| **count).map(|(k, c)| (k.clone(), *c)).unwrap();
+        self.sketch.remove(&min_key);
+        self.sketch.insert(key, min_count + 1);
+    }
+
+    fn distinct(&self) -> String {
+        match &self.exact {
+            Some(counts) => counts.len().to_string(),
+            None => {
+                let kth = *self.kmv.last().unwrap_or(&u64::MAX) as f64;
+                let estimate = (KMV_SIZE - 1) as f64 * u64::MAX as f64 / kth;
+                format!("~{}", estimate.round() as u64)
+            }
+        }
+    }
+
+    fn top_values(&self) -> String {
+        let approximate = self.exact.is_none();
+        let mut top: Vec<(&String, &usize)> = self.exact.as_ref().unwrap_or(&self.sketch).iter().collect();
+        top.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        let top: Vec<String> = top
+            .into_iter()
+            .take(TOP_VALUES)
+            .map(|(value, count)| {
+                let value = match value.char_indices().nth(TOP_VALUE_WIDTH) {
+                    Some((end, _)) => format!("{}…", &value[..end]),
+                    None => value.clone(),
+                };
+                format!("{} ({}{})", value, if approximate { "~" } else { "" }, count)
+            })
+            .collect();
+        top.join(", ")
+    }
+
+    fn min(&self) -> Value {
+        self.min_number.map(format_number).or(self.min_text.clone()).map_or(Value::Null, Value::String)
+    }
+
+    fn max(&self) -> Value {
+        self.max_number.map(format_number).or(self.max_text.clone()).map_or(Value::Null, Value::String)
+    }
+
+    fn mean(&self) -> Value {
+        if self.count == 0 {
+            return Value::Null;
+        }
+        json!((self.sum / self.count as f64 * 100.0).round() / 100.0)
+    }
+}
+
+// Per-column statistics of a result set, as a result set with one row per column.
+// Only the named columns are summarized when any are given.
+pub fn summarize(result: &ResultSet, names: &[String]) -> Result<ResultSet, Box<dyn std::error::Error>> {
+    let mut indexes = vec![];
+    for name in names {
+        let index = result.columns.iter().position(|c| &c.name == name).ok_or_else(|| {
+            let columns: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+            format!("Column '{}' not found in result; columns are: {}", name, columns.join(", "))
+        })?;
+        indexes.push(index);
+    }
+    if indexes.is_empty() {
+        indexes = (0..result.columns.len()).collect();
+    }
+
+    let column = |name: &str, type_name: &str| Column { name: name.to_string(), type_name: type_name.to_string() };
+    let mut summary = ResultSet {
+        columns: vec![
+            column("column", "text"),
+            column("type", "text"),
+            column("nulls", "bigint"),
+            column("distinct", "text"),
+            column("min", "text null"),
+            column("max", "text null"),
+            column("mean", "double null"),
+            column("top_values", "text"),
+        ],
+        rows: vec![],
+        statistics: None,
+    };
+
+    for index in indexes {
+        let numeric = is_numeric_type(&result.columns[index].type_name);
+        let mut stats = ColumnStats::new();
+        for row in &result.rows {
+            stats.observe(row.get(index).unwrap_or(&Value::Null), numeric);
+        }
+
+        summary.rows.push(vec![
+            json!(result.columns[index].name),
+            json!(result.columns[index].type_name),
+            json!(stats.nulls),
+            json!(stats.distinct()),
+            stats.min(),
+            stats.max(),
+            stats.mean(),
+            json!(stats.top_values()),
+        ]);
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orders() -> ResultSet {
+        ResultSet {
+            columns: vec![
+                Column { name: String::from("id"), type_name: String::from("integer") },
+                Column { name: String::from("status"), type_name: String::from("text null") },
+                Column { name: String::from("amount"), type_name: String::from("numeric(10, 2)") },
+            ],
+            rows: vec![
+                vec![json!(1), json!("shipped"), json!("10.50")],
+                vec![json!(2), json!("pending"), json!("3")],
+                vec![json!(3), json!("shipped"), json!(null)],
+                vec![json!(4), json!(null), json!("-1.25")],
+            ],
+            statistics: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&orders(), &[]).unwrap();
+        assert_eq!(summary.columns.len(), 8);
+        assert_eq!(
+            summary.rows[0],
+            vec![json!("id"), json!("integer"), json!(0), json!("4"), json!("1"), json!("4"), json!(2.5), json!("1 (1), 2 (1), 3 (1)")]
+        );
+        assert_eq!(
+            summary.rows[1],
+            vec![
+                json!("status"),
+                json!("text null"),
+                json!(1),
+                json!("2"),
+                json!("pending"),
+                json!("shipped"),
+                Value::Null,
+                json!("shipped (2), pending (1)")
+            ]
+        );
+        assert_eq!(summary.rows[2][4], json!("-1.25"));
+        assert_eq!(summary.rows[2][5], json!("10.5"));
+        assert_eq!(summary.rows[2][6], json!(4.08));
+    }
+
+    #[test]
+    fn test_summarize_selected_columns() {
+        let summary = summarize(&orders(), &[String::from("status")]).unwrap();
+        assert_eq!(summary.rows.len(), 1);
+        assert_eq!(summary.rows[0][0], json!("status"));
+
+        let error = summarize(&orders(), &[String::from("total")]).unwrap_err().to_string();
+        assert_eq!(error, "Column 'total' not found in result; columns are: id, status, amount");
+    }
+
+    #[test]
+    fn test_approximate_distinct_and_top_values() {
+        let mut result = ResultSet {
+            columns: vec![Column { name: String::from("n"), type_name: String::from("bigint") }],
+            rows: vec![],
+            statistics: None,
+        };
+        for i in 0..50_000 {
+            result.rows.push(vec![json!(i)]);
+            if i % 10 == 0 {
+                result.rows.push(vec![json!("7")]);
+            }
+        }
+
+        let summary = summarize(&result, &[]).unwrap();
+        let distinct = summary.rows[0][3].as_str().unwrap();
+        let estimate: f64 = distinct.strip_prefix('~').unwrap().parse().unwrap();
+        assert!((estimate - 50_000.0).abs() / 50_000.0 < 0.1, "{}", distinct);
+        assert!(summary.rows[0][7].as_str().unwrap().starts_with("7 (~"), "{}", summary.rows[0][7]);
+    }
+
+    #[test]
+    fn test_top_value_truncation() {
+        let mut stats = ColumnStats::new();
+        stats.observe(&json!("a very long value that does not fit"), false);
+        assert_eq!(stats.top_values(), "a very long value th… (1)");
+    }
+}
@@ -0,0 +1,225 @@
+use crate::render::value_text;
+use crate::result::ResultSet;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Filter {
+    // JSON string literal, null stays null
+    Json,
+    // POSIX shell single-quoted word
+    Shell,
+    // Replacement for null or empty values
+    Default(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Column { name: String, filters: Vec<Filter> },
+}
+
+// Per-row output template such as "{engine_name}: {status|default:unknown}".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, Box<dyn std::error::Error>> {
+    let filter = filter.trim();
+    let (name, argument) = match filter.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument)),
+        None => (filter, None),
+    };
+
+    match (name, argument) {
+        ("json", None) => Ok(Filter::Json),
+        ("sh", None) => Ok(Filter::Shell),
+        ("default", Some(value)) => Ok(Filter::Default(value.to_string())),
+        ("default", None) => Err("Template filter 'default' needs a value, e.g. {name|default:-}".into()),
+        _ => Err(format!("Unknown template filter '{}': expected json, sh or default:<value>", filter).into()),
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+// Replace \n, \t and \\ in a template given on the command line.
+pub fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+impl Template {
+    // Parse a template; {{ and }} are literal braces.
+    pub fn parse(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(format!("Unterminated placeholder '{{{}' in template", placeholder).into()),
+                        }
+                    }
+
+                    let mut pieces = placeholder.split('|');
+                    let name = pieces.next().unwrap_or_default().trim().to_string();
+                    if name.is_empty() {
+                        return Err("Empty placeholder '{}' in template".into());
+                    }
+                    let filters = pieces.map(parse_filter).collect::<Result<Vec<_>, _>>()?;
+
+                    if !literal.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Column { name, filters });
+                }
+                '}' => return Err("Unmatched '}' in template, use '}}' for a literal brace".into()),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Text(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    // Render every row followed by a newline. All referenced columns must exist in the result.
+    pub fn render(&self, result: &ResultSet) -> Result<String, Box<dyn std::error::Error>> {
+        let mut indexes = vec![];
+        for part in &self.parts {
+            if let Part::Column { name, .. } = part {
+                let index = result.columns.iter().position(|c| &c.name == name).ok_or_else(|| {
+                    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+                    format!("Template column '{}' not found in result; columns are: {}", name, names.join(", "))
+                })?;
+                indexes.push(index);
+            }
+        }
+
+        let mut output = String::new();
+        for row in &result.rows {
+            let mut index = indexes.iter();
+            for part in &self.parts {
+                match part {
+                    Part::Text(text) => output.push_str(text),
+                    Part::Column { filters, .. } => {
+                        let value = row.get(*index.next().unwrap()).filter(|value| !value.is_null());
+                        output.push_str(&apply_filters(value.map(value_text), filters));
+                    }
+                }
+            }
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+}
+
+// Apply filters left to right; None is a null value.
+fn apply_filters(mut value: Option<String>, filters: &[Filter]) -> String {
+    for filter in filters {
+        value = match filter {
+            Filter::Json => Some(match &value {
+                Some(text) => serde_json::Value::String(text.clone()).to_string(),
+                None => String::from("null"),
+            }),
+            Filter::Shell => Some(shell_quote(value.as_deref().unwrap_or_default())),
+            Filter::Default(default) => match value {
+                Some(text) if !text.is_empty() => Some(text),
+                _ => Some(default.clone()),
+            },
+        };
+    }
+    value.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::Column;
+    use serde_json::json;
+
+    fn engines() -> ResultSet {
+        ResultSet {
+            columns: vec![
+                Column { name: String::from("engine_name"), type_name: String::from("text") },
+                Column { name: String::from("status"), type_name: String::from("text null") },
+                Column { name: String::from("nodes"), type_name: String::from("integer") },
+            ],
+            rows: vec![vec![json!("main"), json!("RUNNING"), json!(2)], vec![json!("it's"), json!(null), json!(1)]],
+            statistics: None,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let template = Template::parse("{engine_name}: {status|default:unknown} ({nodes} nodes) {{x}}").unwrap();
+        assert_eq!(
+            template.render(&engines()).unwrap(),
+            "main: RUNNING (2 nodes) {x}\nit's: unknown (1 nodes) {x}\n"
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let template = Template::parse("{engine_name|sh} {status|json} {engine_name|json}").unwrap();
+        assert_eq!(template.render(&engines()).unwrap(), "'main' \"RUNNING\" \"main\"\n'it'\\''s' null \"it's\"\n");
+
+        // Filters apply left to right
+        let template = Template::parse("{status|default:-|sh}").unwrap();
+        assert_eq!(template.render(&engines()).unwrap(), "'RUNNING'\n'-'\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = Template::parse("{name").unwrap_err().to_string();
+        assert!(error.contains("Unterminated"), "{}", error);
+        assert!(Template::parse("{}").is_err());
+        assert!(Template::parse("a } b").is_err());
+        assert!(Template::parse("{name|upper}").is_err());
+        assert!(Template::parse("{name|default}").is_err());
+
+        let error = Template::parse("{engine} {status}").unwrap().render(&engines()).unwrap_err().to_string();
+        assert_eq!(error, "Template column 'engine' not found in result; columns are: engine_name, status, nodes");
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"{a}\t{b}\\n\x"), "{a}\t{b}\\n\\x");
+        assert_eq!(unescape(r"line\n"), "line\n");
+    }
+}
@@ -0,0 +1,182 @@
+use openssl::ssl::{SslConnector, SslMethod};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+use crate::result::{parse_response, Response};
+use crate::utils::format_size;
+
+// Timing report mode of \timing and --timing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Timing {
+    Off,
+    On,
+    Detailed,
+}
+
+impl Timing {
+    pub fn parse(value: &str) -> Option<Timing> {
+        match value.to_lowercase().as_str() {
+            "off" | "false" | "0" => Some(Timing::Off),
+            "on" | "true" | "1" => Some(Timing::On),
+            "detailed" => Some(Timing::Detailed),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Timing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timing::Off => write!(f, "off"),
+            Timing::On => write!(f, "on"),
+            Timing::Detailed => write!(f, "detailed"),
+        }
+    }
+}
+
+// Connection setup times, measured on a separate probe connection to the same endpoint.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectionTiming {
+    pub dns: Duration,
+    pub connect: Duration,
+    pub tls: Option<Duration>,
+}
+
+// The probe gives up after this long, so a slow endpoint does not hold up the query.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Resolve, connect and, for https, complete a TLS handshake with the host of `url`, timing each step.
+pub async fn probe_connection(url: &str) -> Result<ConnectionTiming, Box<dyn std::error::Error>> {
+    tokio::time::timeout(PROBE_TIMEOUT, probe(url))
+        .await
+        .map_err(|_| format!("timed out after {:?}", PROBE_TIMEOUT))?
+}
+
+async fn probe(url: &str) -> Result<ConnectionTiming, Box<dyn std::error::Error>> {
+    let url = reqwest::Url::parse(url)?;
+    let host = url.host_str().ok_or("URL has no host")?.to_string();
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+
+    let start = Instant::now();
+    let address = tokio::net::lookup_host((host.as_str(), port)).await?.next().ok_or("Host did not resolve")?;
+    let dns = start.elapsed();
+
+    let start = Instant::now();
+    let stream = tokio::net::TcpStream::connect(address).await?;
+    let connect = start.elapsed();
+
+    let tls = if url.scheme() == "https" {
+        let stream = stream.into_std()?;
+        stream.set_nonblocking(false)?;
+        // The blocking handshake is not cancelled by the timeout above
+        stream.set_read_timeout(Some(PROBE_TIMEOUT))?;
+        stream.set_write_timeout(Some(PROBE_TIMEOUT))?;
+        let handshake = tokio::task::spawn_blocking(move || -> Result<Duration, String> {
+            let connector = SslConnector::builder(SslMethod::tls()).map_err(|e| e.to_string())?.build();
+            let start = Instant::now();
+            connector.connect(&host, stream).map_err(|e| e.to_string())?;
+            Ok(start.elapsed())
+        });
+        Some(handshake.await??)
+    } else {
+        None
+    };
+
+    Ok(ConnectionTiming { dns, connect, tls })
+}
+
+// Server-reported statistics of a JSONLines_Compact or JSON body, as "name: value" pairs.
+pub fn server_statistics(body: &str) -> Option<String> {
+    let statistics = match parse_response(body) {
+        Some(Response::Result(result)) => result.statistics,
+        _ => serde_json::from_str::<Value>(body).ok().and_then(|json| json.get("statistics").cloned()),
+    }?;
+
+    let pairs: Vec<String> = statistics.as_object()?.iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+    Some(pairs.join(", "))
+}
+
+// Durations are shown with 0.1ms precision, like the Time line.
+fn round(duration: Duration) -> Duration {
+    duration / 100000 * 100000
+}
+
+// Detailed timing lines for a query.
+pub fn format_detailed(
+    connection: &Result<ConnectionTiming, String>,
+    first_byte: Duration,
+    transfer: Duration,
+    body_size: usize,
+    statistics: Option<&str>,
+) -> String {
+    let mut lines = vec![];
+    match connection {
+        Ok(timing) => {
+            let mut line = format!("Connection (probe): DNS {:?}, connect {:?}", round(timing.dns), round(timing.connect));
+            if let Some(tls) = timing.tls {
+                line.push_str(&format!(", TLS {:?}", round(tls)));
+            }
+            lines.push(line);
+        }
+        Err(error) => lines.push(format!("Connection (probe): failed: {}", error)),
+    }
+    lines.push(format!("First byte: {:?}", round(first_byte)));
+    lines.push(format!("Transfer: {:?}, {}", round(transfer), format_size(body_size)));
+    lines.push(format!("Time: {:?}", round(first_byte + transfer)));
+    if let Some(statistics) = statistics {
+        lines.push(format!("Server: {}", statistics));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timing() {
+        assert_eq!(Timing::parse("DETAILED"), Some(Timing::Detailed));
+        assert_eq!(Timing::parse("on"), Some(Timing::On));
+        assert_eq!(Timing::parse("off"), Some(Timing::Off));
+        assert_eq!(Timing::parse("verbose"), None);
+        assert_eq!(Timing::Detailed.to_string(), "detailed");
+    }
+
+    #[test]
+    fn test_server_statistics() {
+        let body = concat!(
+            r#"{"message_type":"START","result_columns":[{"name":"a","type":"int"}]}"#,
+            "\n",
+            r#"{"message_type":"FINISH_SUCCESSFULLY","statistics":{"elapsed":0.012,"rows_read":3}}"#,
+        );
+        assert_eq!(server_statistics(body), Some(String::from("elapsed: 0.012, rows_read: 3")));
+
+        let body = r#"{"meta":[],"data":[],"rows":0,"statistics":{"elapsed":1.5,"bytes_read":100}}"#;
+        assert_eq!(server_statistics(body), Some(String::from("bytes_read: 100, elapsed: 1.5")));
+
+        assert_eq!(server_statistics("a\tb\n1\t2\n"), None);
+    }
+
+    #[test]
+    fn test_format_detailed() {
+        let connection = Ok(ConnectionTiming {
+            dns: Duration::from_micros(1250),
+            connect: Duration::from_millis(10),
+            tls: Some(Duration::from_millis(25)),
+        });
+        let text = format_detailed(&connection, Duration::from_millis(120), Duration::from_millis(30), 2048, Some("elapsed: 0.1"));
+        assert_eq!(
+            text,
+            "Connection (probe): DNS 1.2ms, connect 10ms, TLS 25ms\nFirst byte: 120ms\nTransfer: 30ms, 2KB\nTime: 150ms\nServer: elapsed: 0.1\n"
+        );
+
+        let text = format_detailed(&Err(String::from("refused")), Duration::ZERO, Duration::ZERO, 0, None);
+        assert!(text.starts_with("Connection (probe): failed: refused\n"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_probe_connection_error() {
+        assert!(probe_connection("not a url").await.is_err());
+    }
+}
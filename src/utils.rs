@@ -1,7 +1,9 @@
 use std::fs;
 use std::io::stderr;
+use std::io::IsTerminal;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::SystemTime;
 use tokio::select;
 use tokio_util::sync::CancellationToken;
@@ -32,9 +34,22 @@ pub fn secrets_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(init_root_path()?.join("fb_secrets"))
 }
 
-// Get credentials storage path on disk.
-pub fn credentials_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    Ok(init_root_path()?.join("fb_credentials"))
+// Profile names become part of file names, so they cannot contain path separators.
+pub fn validate_profile(profile: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if profile.contains(['/', '\\']) || profile == "." || profile == ".." {
+        return Err(format!("Invalid profile name '{}'", profile).into());
+    }
+    Ok(())
+}
+
+// Get credentials storage path on disk of a profile; an empty name is the default profile.
+pub fn credentials_path(profile: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    validate_profile(profile)?;
+    if profile.is_empty() {
+        Ok(init_root_path()?.join("fb_credentials"))
+    } else {
+        Ok(init_root_path()?.join(format!("fb_credentials_{}", profile)))
+    }
 }
 
 // Format remaining time for token validity
@@ -44,7 +59,119 @@ pub fn format_remaining_time(time: SystemTime, maybe_more: String) -> Result<Str
         return Ok(format!("{:?} {:} minutes and {:?} seconds", remaining / 60, maybe_more, remaining % 60).to_string());
     }
 
-    return Ok(format!("{:?} {:?} seconds", remaining, maybe_more).to_string());
+    Ok(format!("{:?} {:?} seconds", remaining, maybe_more).to_string())
+}
+
+// Editor command from $VISUAL or $EDITOR, falling back to a platform default.
+pub fn editor_command() -> String {
+    for var in ["VISUAL", "EDITOR"] {
+        if let Ok(editor) = std::env::var(var) {
+            if !editor.trim().is_empty() {
+                return editor;
+            }
+        }
+    }
+
+    if cfg!(windows) {
+        String::from("notepad")
+    } else {
+        String::from("vi")
+    }
+}
+
+// Open file in the editor and wait for it to exit.
+pub fn edit_file(path: &Path, editor: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Like git, run the editor through the shell so it may carry arguments ("code --wait") or a quoted path,
+    // unless it names an existing program whose path has spaces
+    let status = if Path::new(editor).is_file() {
+        Command::new(editor).arg(path).status()?
+    } else if cfg!(windows) {
+        Command::new("cmd").arg("/C").arg(format!("{} \"{}\"", editor, path.display())).status()?
+    } else {
+        Command::new("sh").arg("-c").arg(format!("{} \"$@\"", editor)).arg(editor).arg(path).status()?
+    };
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", editor, status).into());
+    }
+
+    Ok(())
+}
+
+// Edit text in the editor through a temporary .sql file and return the saved text.
+pub fn edit_text(text: &str, editor: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("fb-edit-{}-{}.sql", std::process::id(), rand::random::<u32>()));
+    fs::write(&path, text)?;
+
+    let result = edit_file(&path, editor).and_then(|_| Ok(fs::read_to_string(&path)?));
+    let _ = fs::remove_file(&path);
+
+    result
+}
+
+// Parse a byte size such as 1024, 512KB, 64MB or 1GB (binary units).
+pub fn parse_size(size: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let size = size.trim();
+    let split = size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let invalid = || format!("Invalid size '{}': expected a number with an optional KB, MB or GB suffix", size);
+    let multiplier: usize = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(invalid().into()),
+    };
+    let number: usize = number.parse().map_err(|_| invalid())?;
+
+    Ok(number.checked_mul(multiplier).ok_or_else(invalid)?)
+}
+
+// Format a byte size for messages, e.g. 1.5MB.
+pub fn format_size(bytes: usize) -> String {
+    let units = [(1 << 30, "GB"), (1 << 20, "MB"), (1 << 10, "KB")];
+    for (size, unit) in units {
+        if bytes >= size {
+            let value = format!("{:.1}", bytes as f64 / size as f64);
+            return format!("{}{}", value.trim_end_matches(".0"), unit);
+        }
+    }
+    format!("{}B", bytes)
+}
+
+// Whether the user can answer prompts: both stdin and stderr are terminals.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && stderr().is_terminal()
+}
+
+// Ask a yes/no question on stderr; anything but y or yes is no.
+pub fn confirm(prompt: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    eprint!("{}", prompt);
+    stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Run a command through the system shell with inherited stdio, or an interactive shell if none is given.
+pub fn run_shell(command: Option<&str>) -> Result<std::process::ExitStatus, Box<dyn std::error::Error>> {
+    let status = match command {
+        Some(command) if cfg!(windows) => Command::new("cmd").arg("/C").arg(command).status()?,
+        Some(command) => Command::new("sh").arg("-c").arg(command).status()?,
+        None if cfg!(windows) => Command::new(std::env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd"))).status()?,
+        None => Command::new(std::env::var("SHELL").unwrap_or_else(|_| String::from("sh"))).status()?,
+    };
+
+    Ok(status)
+}
+
+// Quote string as a SQL literal.
+pub fn sql_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+// Quote name as a SQL identifier, keeping its case.
+pub fn sql_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
 }
 
 // Draw spinner until cancelled.
@@ -88,4 +215,64 @@ mod tests {
         let secrets = secrets_path().unwrap();
         assert!(secrets.ends_with("fb_secrets"));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_file_through_shell() {
+        let dir = std::env::temp_dir().join(format!("fb-editor test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("query.sql");
+
+        // Editor arguments are split by the shell
+        fs::write(&file, "SELECT 1;").unwrap();
+        edit_file(&file, "sed -i s/1/2/").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "SELECT 2;");
+
+        // An editor whose path has spaces runs as is
+        let editor = dir.join("my editor.sh");
+        fs::write(&editor, "#!/bin/sh\necho 'SELECT 3;' > \"$1\"\n").unwrap();
+        std::fs::set_permissions(&editor, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        edit_file(&file, editor.to_str().unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "SELECT 3;\n");
+
+        assert!(edit_file(&file, "false").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_profile_credentials_path() {
+        assert!(credentials_path("").unwrap().ends_with("fb_credentials"));
+        assert!(credentials_path("work").unwrap().ends_with("fb_credentials_work"));
+
+        for name in ["../work", "a/b", "a\\b", ".."] {
+            assert_eq!(credentials_path(name).unwrap_err().to_string(), format!("Invalid profile name '{}'", name));
+        }
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("512KB").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("64mb").unwrap(), 64 * 1024 * 1024);
+        assert_eq!(parse_size("1 GB").unwrap(), 1024 * 1024 * 1024);
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("12XB").is_err());
+        assert!(parse_size("99999999999999999999G").is_err());
+        assert!(parse_size(&format!("{}G", usize::MAX >> 20)).unwrap_err().to_string().starts_with("Invalid size"));
+    }
+
+    #[test]
+    fn test_sql_quoting() {
+        assert_eq!(sql_literal("it's"), "'it''s'");
+        assert_eq!(sql_identifier("My \"Engine\""), "\"My \"\"Engine\"\"\"");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(64 * 1024 * 1024), "64MB");
+        assert_eq!(format_size(1536 * 1024), "1.5MB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3GB");
+    }
 }
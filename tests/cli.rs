@@ -1,6 +1,5 @@
 use std::io::Write;
 use std::process::Command;
-use serde_json;
 
 fn run_fb(args: &[&str]) -> (bool, String, String) {
     let output = Command::new(env!("CARGO_BIN_EXE_fb"))
@@ -32,7 +31,7 @@ fn test_set_format() {
 #[test]
 fn test_interactive_mode() {
     let mut child = Command::new(env!("CARGO_BIN_EXE_fb"))
-        .args(&["--core"])
+        .args(["--core"])
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .spawn()
@@ -62,7 +61,7 @@ fn test_interactive_mode() {
 #[test]
 fn test_params_escaping() {
     let mut child = Command::new(env!("CARGO_BIN_EXE_fb"))
-        .args(&[
+        .args([
             "--core",
             "--concise",
             "-f",
@@ -181,7 +180,7 @@ fn test_command_parsing() {
 #[test]
 fn test_exiting() {
     let mut child = Command::new(env!("CARGO_BIN_EXE_fb"))
-        .args(&[
+        .args([
             "--core",
             "--concise",
             "-f",
@@ -268,7 +267,7 @@ fn test_exit_code_on_query_error() {
 fn test_exit_code_on_query_error_interactive() {
     // Test that exit code is non-zero when any query fails in interactive mode
     let mut child = Command::new(env!("CARGO_BIN_EXE_fb"))
-        .args(&["--core", "--concise"])
+        .args(["--core", "--concise"])
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
@@ -288,3 +287,73 @@ fn test_exit_code_on_query_error_interactive() {
         "Exit code should be non-zero when any query in session fails"
     );
 }
+
+#[test]
+fn test_exit_code_on_include_error() {
+    // A failing \i in a piped script makes the exit code non-zero, like a failing query
+    let dir = std::env::temp_dir().join(format!("fb-cli-include-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("outer.sql");
+    std::fs::write(&script, "\\i missing.sql\n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fb"))
+        .args(["--core", "--concise"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "\\i {}", script.display()).unwrap();
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success(), "Exit code should be non-zero when an included file fails");
+    assert!(stderr.contains("missing.sql"), "stderr should name the missing file, got: {}", stderr);
+}
+
+#[test]
+fn test_exit_code_on_unknown_command() {
+    // An unknown meta-command in a piped script makes the exit code non-zero
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fb"))
+        .args(["--core", "--concise"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "\\no_such_command").unwrap();
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success(), "Exit code should be non-zero after an unknown command");
+}
+
+#[test]
+fn test_read_only_checks_every_statement() {
+    // A write after a read in one-shot input is refused, not sent along with the read
+    let (success, _, stderr) = run_fb(&["--core", "--concise", "--read-only", "SELECT 1; DROP TABLE t"]);
+    assert!(!success);
+    assert!(stderr.contains("Read-only mode"), "stderr should refuse the DROP, got: {}", stderr);
+}
+
+#[test]
+fn test_stats_json_line_per_statement() {
+    // One-shot input with two statements writes a stats line for each, failed ones included
+    let path = std::env::temp_dir().join(format!("fb-cli-stats-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    run_fb(&["--core", "--concise", "--stats-json", path.to_str().unwrap(), "-c", "SELECT 42; SELECT 1/0"]);
+
+    let stats = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = stats.lines().collect();
+    assert_eq!(lines.len(), 2, "expected a line per statement, got: {}", stats);
+    assert!(lines[0].starts_with("{\"index\":1,"), "{}", lines[0]);
+    assert!(lines[1].starts_with("{\"index\":2,"), "{}", lines[1]);
+    let _ = std::fs::remove_file(&path);
+}